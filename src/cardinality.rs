@@ -0,0 +1,411 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::influx::{self, InfluxClient};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+// SHOW SERIES is only sampled for the top values drill-down
+const SERIES_SAMPLE: usize = 100_000;
+const TOP_VALUES: usize = 20;
+
+/// A name/count ranking that can be re-sorted by clicking its headers.
+#[derive(Default)]
+pub struct CountTable {
+    pub rows: Vec<(String, u64)>,
+    pub by_name: bool,
+    pub ascending: bool,
+}
+
+impl CountTable {
+    fn set(&mut self, rows: Vec<(String, u64)>) {
+        self.rows = rows;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        if self.by_name {
+            self.rows.sort_by(|a, b| a.0.cmp(&b.0));
+        } else {
+            self.rows.sort_by_key(|r| r.1);
+        }
+        if !self.ascending {
+            self.rows.reverse();
+        }
+    }
+
+    fn toggle(&mut self, by_name: bool) {
+        if self.by_name == by_name {
+            self.ascending = !self.ascending;
+        } else {
+            self.by_name = by_name;
+            self.ascending = by_name;
+        }
+        self.sort();
+    }
+}
+
+pub struct CardinalityView {
+    /// Connection the window was opened on
+    pub connection: u64,
+    pub database: String,
+    pub exact: bool,
+    pub series_total: Option<u64>,
+    pub measurement_total: Option<u64>,
+    pub measurements: CountTable,
+    pub selected_measurement: Option<String>,
+    pub tag_key_total: Option<u64>,
+    pub tag_keys: CountTable,
+    pub selected_tag_key: Option<String>,
+    pub top_values: CountTable,
+    pub sampled_series: usize,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl CardinalityView {
+    pub fn new(connection: u64, database: String) -> Self {
+        Self {
+            connection,
+            database,
+            exact: false,
+            series_total: None,
+            measurement_total: None,
+            measurements: CountTable::default(),
+            selected_measurement: None,
+            tag_key_total: None,
+            tag_keys: CountTable::default(),
+            selected_tag_key: None,
+            top_values: CountTable::default(),
+            sampled_series: 0,
+            loading: false,
+            error: None,
+        }
+    }
+}
+
+enum Pick {
+    Measurement(String),
+    TagKey(String),
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_cardinality_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.cardinality.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(client) = state.connection_of(id).map(|c| c.client.clone()) else {
+            state.cardinality = None;
+            return;
+        };
+        let Some(view) = &mut state.cardinality else {
+            return;
+        };
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut pick = None;
+        let mut export_error = None;
+
+        egui::Window::new(format!("Cardinality: {} - {}", view.database, client.host()))
+            .id(egui::Id::new("cardinality_window"))
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut view.exact, "Exact").changed() {
+                        refresh = true;
+                    }
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    let count = |c: Option<u64>| c.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string());
+                    ui.label(format!("Series: {}", count(view.series_total)));
+                    ui.separator();
+                    ui.label(format!("Measurements: {}", count(view.measurement_total)));
+                    if !view.exact {
+                        ui.weak("(estimated)");
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                ui.separator();
+
+                ui.columns(3, |cols| {
+                    let selected = view.selected_measurement.clone();
+                    if let Some(name) = count_table(
+                        &mut cols[0],
+                        "cardinality_measurements",
+                        ("Measurement", "Series"),
+                        &mut view.measurements,
+                        selected.as_deref(),
+                        &format!("{}_series_cardinality", view.database),
+                        &mut export_error,
+                    ) {
+                        pick = Some(Pick::Measurement(name));
+                    }
+
+                    if let Some(measurement) = &view.selected_measurement {
+                        if let Some(total) = view.tag_key_total {
+                            cols[1].label(format!("{}: {} tag keys", measurement, total));
+                        }
+                        let selected = view.selected_tag_key.clone();
+                        if let Some(key) = count_table(
+                            &mut cols[1],
+                            "cardinality_tag_keys",
+                            ("Tag key", "Values"),
+                            &mut view.tag_keys,
+                            selected.as_deref(),
+                            &format!("{}_{}_tag_cardinality", view.database, measurement),
+                            &mut export_error,
+                        ) {
+                            pick = Some(Pick::TagKey(key));
+                        }
+                    }
+
+                    if let (Some(measurement), Some(key)) = (&view.selected_measurement, &view.selected_tag_key) {
+                        cols[2].label(format!("Top {} by series", key))
+                            .on_hover_text(format!("Counted over the first {} series", view.sampled_series));
+                        count_table(
+                            &mut cols[2],
+                            "cardinality_top_values",
+                            ("Value", "Series"),
+                            &mut view.top_values,
+                            None,
+                            &format!("{}_{}_{}_top_values", view.database, measurement, key),
+                            &mut export_error,
+                        );
+                    }
+                });
+            });
+
+        if export_error.is_some() {
+            view.error = export_error;
+        }
+        if !open {
+            state.cardinality = None;
+            return;
+        }
+
+        let database = view.database.clone();
+        let exact = view.exact;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        if refresh {
+            view.loading = true;
+            self.runtime.spawn(async move {
+                Self::load_cardinality(state_clone, ctx_clone, client, database, exact).await;
+            });
+        } else if let Some(pick) = pick {
+            view.loading = true;
+            match pick {
+                Pick::Measurement(measurement) => {
+                    view.selected_measurement = Some(measurement.clone());
+                    view.selected_tag_key = None;
+                    view.tag_keys.set(Vec::new());
+                    view.tag_key_total = None;
+                    self.runtime.spawn(async move {
+                        Self::load_tag_cardinality(state_clone, ctx_clone, client, database, measurement, exact).await;
+                    });
+                }
+                Pick::TagKey(key) => {
+                    let measurement = view.selected_measurement.clone().unwrap_or_default();
+                    view.selected_tag_key = Some(key.clone());
+                    view.top_values.set(Vec::new());
+                    self.runtime.spawn(async move {
+                        Self::load_top_tag_values(state_clone, ctx_clone, client, database, measurement, key).await;
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) async fn load_cardinality(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        db: String,
+        exact: bool,
+    ) {
+        let result = async {
+            // Estimated counts are queried measurement by measurement
+            let measurements = if exact {
+                Vec::new()
+            } else {
+                client.show_measurements(&db).await?
+            };
+            let series = client.series_cardinality(&db, exact).await?;
+            let measurement_count = client.measurement_cardinality(&db, exact).await?;
+            let ranking = client.series_cardinality_by_measurement(&db, &measurements, exact).await?;
+            anyhow::Ok((series, measurement_count, ranking))
+        }
+        .await;
+
+        Self::with_cardinality_view(&state, &db, |view| match result {
+            Ok((series, measurement_count, ranking)) => {
+                view.series_total = Some(series);
+                view.measurement_total = Some(measurement_count);
+                view.measurements.set(ranking);
+                view.error = None;
+            }
+            Err(e) => view.error = Some(format!("Error: {}", e)),
+        });
+        ctx.request_repaint();
+    }
+
+    async fn load_tag_cardinality(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        db: String,
+        measurement: String,
+        exact: bool,
+    ) {
+        let result = async {
+            let total = client.tag_key_cardinality(&db, &measurement, exact).await?;
+            let mut keys = Vec::new();
+            for key in client.show_tag_keys(&db, &measurement).await? {
+                let count = client.tag_values_cardinality(&db, &measurement, &key, exact).await?;
+                keys.push((key, count));
+            }
+            anyhow::Ok((total, keys))
+        }
+        .await;
+
+        Self::with_cardinality_view(&state, &db, |view| match result {
+            Ok((total, keys)) => {
+                view.tag_key_total = Some(total);
+                view.tag_keys.set(keys);
+                view.error = None;
+            }
+            Err(e) => view.error = Some(format!("Error: {}", e)),
+        });
+        ctx.request_repaint();
+    }
+
+    async fn load_top_tag_values(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        db: String,
+        measurement: String,
+        key: String,
+    ) {
+        let result = client.show_series(&db, &measurement, None, SERIES_SAMPLE, 0).await;
+
+        Self::with_cardinality_view(&state, &db, |view| match result {
+            Ok(series) => {
+                let mut counts: HashMap<String, u64> = HashMap::new();
+                for series_key in &series {
+                    let (_, tags) = influx::parse_series_key(series_key);
+                    if let Some((_, value)) = tags.into_iter().find(|(k, _)| k == &key) {
+                        *counts.entry(value).or_default() += 1;
+                    }
+                }
+
+                let mut top: Vec<(String, u64)> = counts.into_iter().collect();
+                top.sort_by_key(|r| std::cmp::Reverse(r.1));
+                top.truncate(TOP_VALUES);
+
+                view.sampled_series = series.len();
+                view.top_values.set(top);
+                view.error = None;
+            }
+            Err(e) => view.error = Some(format!("Error: {}", e)),
+        });
+        ctx.request_repaint();
+    }
+
+    fn with_cardinality_view(state: &Arc<Mutex<AppState>>, db: &str, f: impl FnOnce(&mut CardinalityView)) {
+        let mut state = state.lock().unwrap();
+        // Ignore results for a window that was closed or reopened on another database
+        if let Some(view) = state.cardinality.as_mut().filter(|v| v.database == db) {
+            view.loading = false;
+            f(view);
+        }
+    }
+}
+
+/// Renders a ranking with sortable headers; returns the name of a clicked row.
+fn count_table(
+    ui: &mut egui::Ui,
+    id: &str,
+    headers: (&str, &str),
+    table: &mut CountTable,
+    selected: Option<&str>,
+    export_name: &str,
+    export_error: &mut Option<String>,
+) -> Option<String> {
+    let mut clicked = None;
+
+    ui.push_id(id, |ui| {
+        ui.horizontal(|ui| {
+            let (sorted_by_name, ascending) = (table.by_name, table.ascending);
+            let arrow = |by_name: bool| {
+                if sorted_by_name != by_name {
+                    ""
+                } else if ascending {
+                    " ^"
+                } else {
+                    " v"
+                }
+            };
+            if ui.button(format!("{}{}", headers.0, arrow(true))).clicked() {
+                table.toggle(true);
+            }
+            if ui.button(format!("{}{}", headers.1, arrow(false))).clicked() {
+                table.toggle(false);
+            }
+            if ui.add_enabled(!table.rows.is_empty(), egui::Button::new("Export")).clicked() {
+                if let Err(e) = export_counts(export_name, headers, &table.rows) {
+                    *export_error = Some(format!("Error: {}", e));
+                }
+            }
+        });
+
+        egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+            egui::Grid::new(id).striped(true).num_columns(2).show(ui, |ui| {
+                for (name, count) in &table.rows {
+                    if ui.selectable_label(selected == Some(name.as_str()), name).clicked() {
+                        clicked = Some(name.clone());
+                    }
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+    });
+
+    clicked
+}
+
+/// Writes the ranking to a CSV file the user picks; cancelling the dialog is not an error.
+fn export_counts(name: &str, headers: (&str, &str), rows: &[(String, u64)]) -> csv::Result<()> {
+    let filename = format!("{}_{}.csv", name, chrono::Local::now().format("%Y%m%d_%H%M%S"));
+
+    if let Some(path) = rfd::FileDialog::new()
+        .set_file_name(&filename)
+        .save_file()
+    {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record([headers.0, headers.1])?;
+
+        for (name, count) in rows {
+            writer.write_record([name.as_str(), &count.to_string()])?;
+        }
+
+        writer.flush()?;
+    }
+    Ok(())
+}
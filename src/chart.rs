@@ -0,0 +1,222 @@
+use eframe::egui::{self, Color32, Pos2, Rect, Stroke};
+use serde_json::Value;
+
+const PALETTE: [Color32; 8] = [
+    Color32::from_rgb(86, 156, 214),
+    Color32::from_rgb(220, 120, 60),
+    Color32::from_rgb(80, 180, 80),
+    Color32::from_rgb(200, 80, 160),
+    Color32::from_rgb(220, 190, 60),
+    Color32::from_rgb(120, 110, 220),
+    Color32::from_rgb(60, 190, 190),
+    Color32::from_rgb(180, 180, 180),
+];
+
+/// One named series of `[unix seconds, value]` points, sorted by time.
+pub struct Line {
+    pub name: String,
+    pub points: Vec<[f64; 2]>,
+}
+
+/// A highlighted time span drawn behind the lines.
+pub struct Mark {
+    pub start: f64,
+    pub end: f64,
+    pub color: Color32,
+    pub label: String,
+}
+
+// Points per line actually drawn; longer results are sampled evenly
+const MAX_POINTS: usize = 2000;
+
+pub fn color(index: usize) -> Color32 {
+    PALETTE[index % PALETTE.len()]
+}
+
+/// Compact numbers for axis labels: 1.2k, 3.4M, ...
+pub fn format_number(value: f64) -> String {
+    let abs = value.abs();
+    let (scaled, suffix) = if abs >= 1e12 {
+        (value / 1e12, "T")
+    } else if abs >= 1e9 {
+        (value / 1e9, "G")
+    } else if abs >= 1e6 {
+        (value / 1e6, "M")
+    } else if abs >= 1e3 {
+        (value / 1e3, "k")
+    } else {
+        (value, "")
+    };
+    if suffix.is_empty() && scaled.fract() != 0.0 && abs < 100.0 {
+        format!("{:.2}", scaled)
+    } else {
+        let text = format!("{:.1}", scaled);
+        format!("{}{}", text.strip_suffix(".0").unwrap_or(&text), suffix)
+    }
+}
+
+/// Unix seconds of a `time` value, RFC3339 or integer nanoseconds.
+pub fn time_secs(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.timestamp_nanos_opt().unwrap_or(0) as f64 / 1e9),
+        Value::Number(n) => n.as_f64().map(|ns| ns / 1e9),
+        _ => None,
+    }
+}
+
+/// Columns whose first non-null value is a number, except `time`.
+pub fn numeric_columns(columns: &[String], rows: &[Vec<Value>]) -> Vec<String> {
+    columns
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            c.as_str() != "time"
+                && rows
+                    .iter()
+                    .filter_map(|r| r.get(*i))
+                    .find(|v| !v.is_null())
+                    .is_some_and(|v| v.is_number())
+        })
+        .map(|(_, c)| c.clone())
+        .collect()
+}
+
+/// One line per selected column of a result with a `time` column.
+pub fn lines_from_rows(columns: &[String], rows: &[Vec<Value>], selected: &[String]) -> Vec<Line> {
+    let Some(time) = columns.iter().position(|c| c == "time") else {
+        return Vec::new();
+    };
+    let step = rows.len().div_ceil(MAX_POINTS).max(1);
+
+    selected
+        .iter()
+        .filter_map(|name| {
+            let idx = columns.iter().position(|c| c == name)?;
+            let points = rows
+                .iter()
+                .step_by(step)
+                .filter_map(|row| Some([time_secs(row.get(time)?)?, row.get(idx)?.as_f64()?]))
+                .collect();
+            Some(Line {
+                name: name.clone(),
+                points,
+            })
+        })
+        .collect()
+}
+
+pub fn format_time(secs: f64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// A time series chart drawn with the painter; hovering shows the values closest to the pointer.
+pub fn line_chart(ui: &mut egui::Ui, lines: &[Line], height: f32) {
+    marked_line_chart(ui, lines, &[], height);
+}
+
+/// Like `line_chart`, with time spans shaded behind the lines.
+pub fn marked_line_chart(ui: &mut egui::Ui, lines: &[Line], marks: &[Mark], height: f32) {
+    let width = ui.available_width().max(200.0);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    let text_color = visuals.weak_text_color();
+    let font = egui::FontId::proportional(11.0);
+
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let points = lines.iter().flat_map(|l| l.points.iter());
+    let (mut x_min, mut x_max, mut y_min, mut y_max) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+    for [x, y] in points {
+        x_min = x_min.min(*x);
+        x_max = x_max.max(*x);
+        y_min = y_min.min(*y);
+        y_max = y_max.max(*y);
+    }
+    if x_min > x_max {
+        painter.text(rect.center(), egui::Align2::CENTER_CENTER, "No data", font, text_color);
+        return;
+    }
+    if y_min == y_max {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+    if x_min == x_max {
+        x_max += 1.0;
+    }
+
+    let plot = Rect::from_min_max(rect.min + egui::vec2(50.0, 8.0), rect.max - egui::vec2(8.0, 18.0));
+    let to_screen = |x: f64, y: f64| {
+        Pos2::new(
+            plot.left() + ((x - x_min) / (x_max - x_min)) as f32 * plot.width(),
+            plot.bottom() - ((y - y_min) / (y_max - y_min)) as f32 * plot.height(),
+        )
+    };
+
+    let grid = Stroke::new(1.0, visuals.faint_bg_color);
+    for i in 0..=4 {
+        let y = y_min + (y_max - y_min) * i as f64 / 4.0;
+        let pos = to_screen(x_min, y);
+        painter.line_segment([Pos2::new(plot.left(), pos.y), Pos2::new(plot.right(), pos.y)], grid);
+        painter.text(
+            Pos2::new(plot.left() - 4.0, pos.y),
+            egui::Align2::RIGHT_CENTER,
+            format_number(y),
+            font.clone(),
+            text_color,
+        );
+    }
+    painter.text(plot.left_bottom() + egui::vec2(0.0, 2.0), egui::Align2::LEFT_TOP, format_time(x_min), font.clone(), text_color);
+    painter.text(plot.right_bottom() + egui::vec2(0.0, 2.0), egui::Align2::RIGHT_TOP, format_time(x_max), font.clone(), text_color);
+
+    for mark in marks {
+        let (left, right) = (to_screen(mark.start, y_min).x, to_screen(mark.end, y_min).x);
+        let span = Rect::from_x_y_ranges(left.max(plot.left())..=right.min(plot.right()).max(left + 2.0), plot.y_range());
+        painter.rect_filled(span, 0.0, mark.color.linear_multiply(0.25));
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        let screen: Vec<Pos2> = line.points.iter().map(|[x, y]| to_screen(*x, *y)).collect();
+        if screen.len() == 1 {
+            painter.circle_filled(screen[0], 2.0, color(i));
+        } else {
+            painter.add(egui::Shape::line(screen, Stroke::new(1.5, color(i))));
+        }
+    }
+
+    if let Some(pointer) = response.hover_pos().filter(|p| plot.contains(*p)) {
+        painter.line_segment(
+            [Pos2::new(pointer.x, plot.top()), Pos2::new(pointer.x, plot.bottom())],
+            Stroke::new(1.0, text_color),
+        );
+        let x = x_min + ((pointer.x - plot.left()) / plot.width()) as f64 * (x_max - x_min);
+
+        response.on_hover_ui_at_pointer(|ui| {
+            ui.label(format_time(x));
+            for mark in marks.iter().filter(|m| m.start <= x && x <= m.end) {
+                ui.colored_label(mark.color, &mark.label);
+            }
+            for (i, line) in lines.iter().enumerate() {
+                let nearest = line
+                    .points
+                    .iter()
+                    .min_by(|a, b| (a[0] - x).abs().total_cmp(&(b[0] - x).abs()));
+                if let Some([_, y]) = nearest {
+                    ui.colored_label(color(i), format!("{}: {}", line.name, format_number(*y)));
+                }
+            }
+        });
+    }
+
+    if lines.len() > 1 {
+        ui.horizontal_wrapped(|ui| {
+            for (i, line) in lines.iter().enumerate() {
+                ui.colored_label(color(i), format!("— {}", line.name));
+            }
+        });
+    }
+}
@@ -0,0 +1,250 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::influx::InfluxClient;
+use crate::schema::{self, DiffKind, DiffNode};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+/// Each side is an open connection and one of its databases.
+pub struct SchemaCompareView {
+    pub left: Option<u64>,
+    pub left_db: String,
+    pub right: Option<u64>,
+    pub right_db: String,
+    /// Hosts the result was compared on, for the report
+    pub left_host: String,
+    pub right_host: String,
+    pub only_differences: bool,
+    pub result: Option<Vec<DiffNode>>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl SchemaCompareView {
+    pub fn new(connection: Option<u64>, db: Option<&str>) -> Self {
+        Self {
+            left: connection,
+            left_db: db.unwrap_or_default().to_string(),
+            right: connection,
+            right_db: String::new(),
+            left_host: String::new(),
+            right_host: String::new(),
+            only_differences: true,
+            result: None,
+            loading: false,
+            error: None,
+        }
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_compare_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let connections: Vec<(u64, String, Vec<String>)> = state
+            .connections
+            .iter()
+            .map(|c| (c.id, c.host.clone(), c.databases.clone()))
+            .collect();
+        let Some(view) = &mut state.schema_compare else {
+            return;
+        };
+
+        let mut open = true;
+        let mut run = false;
+
+        egui::Window::new("Compare schemas")
+            .open(&mut open)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("compare_form").num_columns(3).show(ui, |ui| {
+                    ui.label("");
+                    ui.strong("Connection");
+                    ui.strong("Database");
+                    ui.end_row();
+
+                    ui.label("Left:");
+                    side_picker(ui, "compare_left", &connections, &mut view.left, &mut view.left_db);
+                    ui.end_row();
+
+                    ui.label("Right:");
+                    side_picker(ui, "compare_right", &connections, &mut view.right, &mut view.right_db);
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    let ready = !view.loading
+                        && view.left.is_some()
+                        && view.right.is_some()
+                        && !view.left_db.is_empty()
+                        && !view.right_db.is_empty();
+                    if ui.add_enabled(ready, egui::Button::new("Compare")).clicked() {
+                        run = true;
+                    }
+                    ui.checkbox(&mut view.only_differences, "Only differences");
+                    if let Some(result) = &view.result {
+                        if ui.button("Export report").clicked() {
+                            if let Err(e) = export_report(view, result) {
+                                view.error = Some(format!("Error: {}", e));
+                            }
+                        }
+                    }
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                ui.separator();
+
+                if let Some(result) = &view.result {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for node in result {
+                            diff_tree(ui, node, view.only_differences);
+                        }
+                    });
+                }
+            });
+
+        if !open {
+            state.schema_compare = None;
+            return;
+        }
+
+        if run {
+            let client = |id: Option<u64>| {
+                id.and_then(|id| state.connections.iter().find(|c| c.id == id))
+                    .map(|c| c.client.clone())
+            };
+            let (Some(left), Some(right)) = (client(view.left), client(view.right)) else {
+                view.error = Some("Error: the connection is gone".to_string());
+                return;
+            };
+            view.loading = true;
+            view.error = None;
+            view.left_host = left.host().to_string();
+            view.right_host = right.host().to_string();
+
+            let left = (left, view.left_db.clone());
+            let right = (right, view.right_db.clone());
+            let state_clone = Arc::clone(&self.state);
+            let ctx_clone = ctx.clone();
+
+            self.runtime.spawn(async move {
+                Self::run_schema_compare(state_clone, ctx_clone, left, right).await;
+            });
+        }
+    }
+
+    async fn run_schema_compare(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        left: (InfluxClient, String),
+        right: (InfluxClient, String),
+    ) {
+        let result = async {
+            let l = schema::load_database_schema(&left.0, &left.1).await?;
+            let r = schema::load_database_schema(&right.0, &right.1).await?;
+            anyhow::Ok(schema::diff_schemas(&l, &r))
+        }
+        .await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.schema_compare {
+                view.loading = false;
+                match result {
+                    Ok(diff) => view.result = Some(diff),
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// Connection and database of one side; databases are the ones listed in the tree.
+fn side_picker(
+    ui: &mut egui::Ui,
+    id: &str,
+    connections: &[(u64, String, Vec<String>)],
+    connection: &mut Option<u64>,
+    db: &mut String,
+) {
+    let selected = connections.iter().find(|(c, _, _)| Some(*c) == *connection);
+    egui::ComboBox::from_id_salt((id, "connection"))
+        .selected_text(selected.map(|(_, host, _)| host.as_str()).unwrap_or("-"))
+        .show_ui(ui, |ui| {
+            for (c, host, _) in connections {
+                if ui.selectable_value(connection, Some(*c), host).changed() {
+                    db.clear();
+                }
+            }
+        });
+    egui::ComboBox::from_id_salt((id, "database"))
+        .selected_text(db.as_str())
+        .show_ui(ui, |ui| {
+            for name in selected.map(|(_, _, dbs)| dbs.as_slice()).unwrap_or_default() {
+                ui.selectable_value(db, name.clone(), name);
+            }
+        });
+}
+
+fn diff_tree(ui: &mut egui::Ui, node: &DiffNode, only_differences: bool) {
+    if only_differences && node.kind == DiffKind::Same {
+        return;
+    }
+
+    let color = match node.kind {
+        DiffKind::Same => ui.visuals().text_color(),
+        DiffKind::Added => egui::Color32::from_rgb(80, 180, 80),
+        DiffKind::Removed => egui::Color32::from_rgb(220, 80, 80),
+        DiffKind::Changed => egui::Color32::from_rgb(220, 170, 60),
+    };
+    let mut text = format!("{} {}", node.kind.symbol(), node.label);
+    if !node.detail.is_empty() {
+        text.push_str(&format!("  ({})", node.detail));
+    }
+    let text = egui::RichText::new(text).color(color).monospace();
+
+    if node.children.is_empty() {
+        ui.label(text);
+    } else {
+        egui::CollapsingHeader::new(text)
+            .id_salt(ui.id().with(&node.label))
+            .default_open(node.kind != DiffKind::Same)
+            .show(ui, |ui| {
+                for child in &node.children {
+                    diff_tree(ui, child, only_differences);
+                }
+            });
+    }
+}
+
+/// Writes the diff to a file the user picks; cancelling the dialog is not an error.
+fn export_report(view: &SchemaCompareView, result: &[DiffNode]) -> std::io::Result<()> {
+    let filename = format!(
+        "schema_diff_{}_{}_{}.txt",
+        view.left_db,
+        view.right_db,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+
+    if let Some(path) = rfd::FileDialog::new()
+        .set_file_name(&filename)
+        .save_file()
+    {
+        let mut report = format!(
+            "Schema diff\nLeft:  {} / {}\nRight: {} / {}\n(+ only on the right, - only on the left, ~ changed)\n\n",
+            view.left_host, view.left_db, view.right_host, view.right_db
+        );
+        for node in result {
+            node.write_report(&mut report, 0, view.only_differences);
+        }
+
+        std::fs::write(path, report)?;
+    }
+    Ok(())
+}
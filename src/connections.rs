@@ -0,0 +1,770 @@
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::cardinality::CardinalityView;
+use crate::continuous_query::CqEditor;
+use crate::credentials::{CredentialRef, Credentials};
+use crate::highlight;
+use crate::influx::{ContinuousQuery, InfluxClient, RetentionPolicy};
+use crate::server::{Capabilities, ServerInfo};
+use crate::ui::{AdminAction, AdminDialog, AppState, QueryLanguage, RetentionPolicyAction, RetentionPolicyDialog};
+use crate::InfluxDBApp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connecting,
+    /// Restored with credentials in the vault, waiting for its passphrase
+    Locked,
+    Connected,
+    Failed(String),
+}
+
+/// One open server in the sidebar tree, with whatever has been listed from it so far.
+pub struct Connection {
+    pub id: u64,
+    pub host: String,
+    pub proxy: Option<String>,
+    pub client: InfluxClient,
+    /// Kept in memory only, for reconnecting
+    pub credentials: Option<Credentials>,
+    /// Where the credentials were saved, if they were
+    pub credential: Option<CredentialRef>,
+    /// Disables everything that creates or drops data on this server
+    pub read_only: bool,
+    pub server_info: Option<ServerInfo>,
+    pub status: ConnectionStatus,
+    pub databases: Vec<String>,
+    /// Filled per database the first time it is opened
+    pub retention_policies: HashMap<String, Vec<RetentionPolicy>>,
+    pub measurements: HashMap<String, Vec<String>>,
+    pub loading: HashSet<String>,
+}
+
+impl Connection {
+    pub fn new(id: u64, host: String, proxy: Option<String>, credentials: Option<Credentials>) -> Self {
+        Self {
+            id,
+            client: InfluxClient::new(host.clone(), proxy.clone()).with_credentials(credentials.clone()),
+            host,
+            proxy,
+            credentials,
+            credential: None,
+            read_only: false,
+            server_info: None,
+            status: ConnectionStatus::Connecting,
+            databases: Vec::new(),
+            retention_policies: HashMap::new(),
+            measurements: HashMap::new(),
+            loading: HashSet::new(),
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.server_info
+            .as_ref()
+            .map(|info| info.capabilities())
+            .unwrap_or(Capabilities::ALL)
+    }
+
+    fn status_color(&self) -> Color32 {
+        match self.status {
+            ConnectionStatus::Connecting => Color32::from_rgb(220, 190, 60),
+            ConnectionStatus::Locked => Color32::GRAY,
+            ConnectionStatus::Connected => Color32::from_rgb(80, 180, 80),
+            ConnectionStatus::Failed(_) => Color32::from_rgb(220, 80, 80),
+        }
+    }
+
+    fn status_text(&self) -> String {
+        let status = match &self.status {
+            ConnectionStatus::Connecting => "Connecting...".to_string(),
+            ConnectionStatus::Locked => "Locked, unlock the credential vault to connect".to_string(),
+            ConnectionStatus::Connected => format!("Connected, {} databases", self.databases.len()),
+            ConnectionStatus::Failed(e) => format!("Error: {}", e),
+        };
+        let status = if self.read_only { format!("{}\nRead-only", status) } else { status };
+        let status = match &self.credential {
+            Some(reference) => format!("{}\nCredentials saved in the {}", status, reference.store.label()),
+            None => status,
+        };
+        match &self.server_info {
+            Some(info) => format!("{}\n{}\n{}", status, info.label(), info.capabilities().summary()),
+            None => status,
+        }
+    }
+}
+
+/// A client for `host`, authenticated like the open connection to it if there is one.
+pub fn client_for(connections: &[Connection], host: String, proxy: Option<String>) -> InfluxClient {
+    let credentials = connections.iter().find(|c| c.host == host).and_then(|c| c.credentials.clone());
+    InfluxClient::new(host, proxy).with_credentials(credentials)
+}
+
+/// What a click in the tree asks for; applied once the tree is drawn.
+enum TreeAction {
+    SelectConnection(u64),
+    Reconnect(u64),
+    Disconnect(u64),
+    ForgetCredentials(u64),
+    ToggleReadOnly(u64),
+    OpenDatabase { connection: u64, db: String, select: bool },
+    SelectRetentionPolicy { connection: u64, db: String, rp: String },
+    OpenMeasurement { connection: u64, db: String, rp: Option<String>, measurement: String, new_tab: bool },
+    Cardinality { connection: u64, db: String },
+    Admin { connection: u64, action: AdminAction },
+    RetentionPolicy { connection: u64, action: RetentionPolicyAction, db: String, policy: RetentionPolicy },
+    Downsample { connection: u64, db: String, measurement: String },
+    Gaps { connection: u64, db: String, measurement: String },
+    EditContinuousQuery { connection: u64, db: String, cq: Option<ContinuousQuery> },
+}
+
+/// A tree node with a selectable label; clicking the label also expands the node.
+fn tree_node(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    selected: bool,
+    label: impl Into<egui::WidgetText>,
+    add_body: impl FnOnce(&mut egui::Ui),
+) -> egui::Response {
+    let (_, header, _) = CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| ui.selectable_label(selected, label))
+        .body(add_body);
+    if header.inner.clicked() {
+        if let Some(mut collapsing) = CollapsingState::load(ui.ctx(), id) {
+            collapsing.set_open(true);
+            collapsing.store(ui.ctx());
+        }
+    }
+    header.inner
+}
+
+impl InfluxDBApp {
+    /// Adds a connection for `host`, or reconnects the one already open for it, keeping its read-only flag.
+    pub(crate) fn connect(
+        &self,
+        ctx: &egui::Context,
+        state: &mut AppState,
+        host: String,
+        proxy: Option<String>,
+        credentials: Option<Credentials>,
+        credential: Option<CredentialRef>,
+    ) -> u64 {
+        let mut connection = Connection::new(0, host, proxy, credentials);
+        connection.credential = credential;
+        let id = match state.connections.iter_mut().find(|c| c.host == connection.host) {
+            Some(existing) => {
+                connection.id = existing.id;
+                connection.read_only = existing.read_only;
+                *existing = connection;
+                existing.id
+            }
+            None => {
+                state.connection_counter += 1;
+                connection.id = state.connection_counter;
+                state.connections.push(connection);
+                state.connection_counter
+            }
+        };
+        state.active_connection = None;
+        state.select_connection(id);
+
+        let Some(client) = state.connection_by_id(id).map(|c| c.client.clone()) else {
+            return id;
+        };
+        state.status = format!("Connecting to {}...", client.host());
+        state.is_loading = true;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            Self::load_connection(state_clone, ctx_clone, id, client).await;
+        });
+        id
+    }
+
+    /// Connects again with the same host, proxy and credentials.
+    pub(crate) fn reconnect(&self, ctx: &egui::Context, state: &mut AppState, id: u64) {
+        if let Some(connection) = state.connection_by_id(id) {
+            let (host, proxy) = (connection.host.clone(), connection.proxy.clone());
+            let (credentials, credential) = (connection.credentials.clone(), connection.credential.clone());
+            self.connect(ctx, state, host, proxy, credentials, credential);
+        }
+    }
+
+    async fn load_connection(state: Arc<Mutex<AppState>>, ctx: egui::Context, id: u64, client: InfluxClient) {
+        // A failed ping isn't fatal, some proxies don't forward /ping
+        let mut server_info = client.ping().await.ok();
+        if let Some(info) = server_info.as_mut().filter(|info| info.needs_flux_probe()) {
+            info.flux_enabled = client.flux_enabled().await;
+        }
+        let is_v3 = server_info
+            .as_ref()
+            .map(|info| info.capabilities().sql)
+            .unwrap_or(false);
+
+        // InfluxDB 3.x may not answer SHOW DATABASES, fall back to its own API
+        let dbs = if is_v3 {
+            client.show_databases_v3().await
+        } else {
+            match client.show_databases().await {
+                Ok(dbs) => Ok(dbs),
+                Err(e) => client.show_databases_v3().await.map_err(|_| e),
+            }
+        };
+
+        {
+            let mut state = state.lock().unwrap();
+            state.is_loading = false;
+            let active = state.active_connection == Some(id);
+            let Some(connection) = state.connection_by_id(id) else {
+                return;
+            };
+            connection.server_info = server_info;
+
+            match dbs {
+                Ok(dbs) => {
+                    connection.status = ConnectionStatus::Connected;
+                    connection.databases = dbs.clone();
+                    state.status = format!("Connected to {}: {} databases", client.host(), dbs.len());
+
+                    // Tabs without a connection yet use the new server, tabs on this host the fresh client
+                    for tab in &mut state.tabs {
+                        if tab.client.is_none() || tab.host() == Some(client.host()) {
+                            tab.client = Some(client.clone());
+                            tab.database = tab.database.take().filter(|db| dbs.contains(db));
+                        }
+                    }
+
+                    // Don't leave the editor in a mode this server can't run
+                    if active {
+                        let supported = QueryLanguage::ALL.map(|l| state.language_supported(l));
+                        for tab in &mut state.tabs {
+                            let index = QueryLanguage::ALL.iter().position(|l| *l == tab.query_language).unwrap_or(0);
+                            if !supported[index] {
+                                tab.query_language = QueryLanguage::InfluxQL;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    connection.status = ConnectionStatus::Failed(e.to_string());
+                    state.status = format!("Error: {}", e);
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    pub(crate) fn show_connections_panel(&self, ctx: &egui::Context, state: &mut AppState) {
+        let mut action = None;
+
+        egui::SidePanel::left("connections_panel")
+            .default_width(260.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Connections");
+                    let administrable = state.active_connection.filter(|_| state.can_administer());
+                    if let Some(connection) = administrable {
+                        if ui.small_button("+").on_hover_text("Create database on the active connection").clicked() {
+                            state.admin_dialog = Some(AdminDialog {
+                                connection,
+                                action: AdminAction::CreateDatabase {
+                                    name: String::new(),
+                                    with_rp: false,
+                                    rp: RetentionPolicy {
+                                        duration: "INF".to_string(),
+                                        replication: 1,
+                                        ..Default::default()
+                                    },
+                                },
+                                confirm: String::new(),
+                            });
+                        }
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if state.connections.is_empty() {
+                        ui.weak("Enter a host and hit Connect");
+                    }
+                    for connection in &state.connections {
+                        Self::show_connection_node(ui, state, connection, &mut action);
+                    }
+                });
+            });
+
+        if let Some(action) = action {
+            self.apply_tree_action(ctx, state, action);
+        }
+    }
+
+    fn show_connection_node(ui: &mut egui::Ui, state: &AppState, connection: &Connection, action: &mut Option<TreeAction>) {
+        let id = connection.id;
+        let is_active = state.active_connection == Some(id);
+        let caps = connection.capabilities();
+
+        let (_, header, _) = CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id(("connection", id)), true)
+            .show_header(ui, |ui| {
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                ui.painter().circle_filled(rect.center(), 4.0, connection.status_color());
+                let response = ui
+                    .selectable_label(is_active, egui::RichText::new(&connection.host).strong())
+                    .on_hover_text(connection.status_text());
+                if connection.status == ConnectionStatus::Connecting {
+                    ui.spinner();
+                }
+                response
+            })
+            .body(|ui| {
+                if let ConnectionStatus::Failed(e) = &connection.status {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                }
+
+                for db in &connection.databases {
+                    let db_selected = is_active && state.selected_db.as_ref() == Some(db);
+                    let response = tree_node(ui, ui.make_persistent_id(("database", id, db)), db_selected, db, |ui| {
+                        Self::show_database_body(ui, state, connection, db, action);
+                    });
+
+                    if response.clicked() {
+                        *action = Some(TreeAction::OpenDatabase { connection: id, db: db.clone(), select: true });
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("Refresh").clicked() {
+                            *action = Some(TreeAction::OpenDatabase { connection: id, db: db.clone(), select: db_selected });
+                            ui.close_menu();
+                        }
+                        if ui.button("Cardinality...").clicked() {
+                            *action = Some(TreeAction::Cardinality { connection: id, db: db.clone() });
+                            ui.close_menu();
+                        }
+                        if !connection.read_only && caps.manage_databases && ui.button("Drop database...").clicked() {
+                            *action = Some(TreeAction::Admin {
+                                connection: id,
+                                action: AdminAction::DropDatabase { name: db.clone() },
+                            });
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+
+        let response = header.inner;
+        if response.clicked() {
+            *action = Some(TreeAction::SelectConnection(id));
+        }
+        response.context_menu(|ui| {
+            if ui.button("Reconnect").clicked() {
+                *action = Some(TreeAction::Reconnect(id));
+                ui.close_menu();
+            }
+            if ui.button("Disconnect").clicked() {
+                *action = Some(TreeAction::Disconnect(id));
+                ui.close_menu();
+            }
+            let mut read_only = connection.read_only;
+            if ui
+                .checkbox(&mut read_only, "Read-only")
+                .on_hover_text("Disable everything that creates or drops data on this server")
+                .clicked()
+            {
+                *action = Some(TreeAction::ToggleReadOnly(id));
+                ui.close_menu();
+            }
+            if connection.credential.is_some() && ui.button("Forget saved credentials").clicked() {
+                *action = Some(TreeAction::ForgetCredentials(id));
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn show_database_body(ui: &mut egui::Ui, state: &AppState, connection: &Connection, db: &String, action: &mut Option<TreeAction>) {
+        let id = connection.id;
+        let caps = connection.capabilities();
+        let can_edit_rps = caps.retention_policies && !connection.read_only;
+        let is_selected = state.active_connection == Some(id) && state.selected_db.as_ref() == Some(db);
+
+        let loaded = connection.measurements.contains_key(db);
+        if !loaded && !connection.loading.contains(db) && action.is_none() {
+            // Expanded with the arrow rather than by clicking the name
+            *action = Some(TreeAction::OpenDatabase { connection: id, db: db.clone(), select: false });
+        }
+        if connection.loading.contains(db) {
+            ui.spinner();
+        }
+
+        let policies = connection
+            .retention_policies
+            .get(db)
+            .filter(|p| caps.retention_policies && !p.is_empty());
+
+        match policies {
+            Some(policies) => {
+                for rp in policies {
+                    let label = if rp.default {
+                        format!("{} (default)", rp.name)
+                    } else {
+                        rp.name.clone()
+                    };
+                    let rp_selected = is_selected && state.selected_rp.as_ref() == Some(&rp.name);
+                    let response = tree_node(ui, ui.make_persistent_id(("retention_policy", id, db, &rp.name)), rp_selected, label, |ui| {
+                        Self::show_measurement_nodes(ui, state, connection, db, Some(&rp.name), action);
+                    })
+                    .on_hover_text(format!(
+                        "Duration: {}\nShard group duration: {}\nReplication: {}",
+                        rp.duration, rp.shard_group_duration, rp.replication
+                    ));
+
+                    if response.clicked() {
+                        *action = Some(TreeAction::SelectRetentionPolicy { connection: id, db: db.clone(), rp: rp.name.clone() });
+                    }
+
+                    if !can_edit_rps {
+                        continue;
+                    }
+
+                    response.context_menu(|ui| {
+                        for (label, rp_action) in [("Alter...", RetentionPolicyAction::Alter), ("Drop...", RetentionPolicyAction::Drop)] {
+                            if ui.button(label).clicked() {
+                                *action = Some(TreeAction::RetentionPolicy {
+                                    connection: id,
+                                    action: rp_action,
+                                    db: db.clone(),
+                                    policy: rp.clone(),
+                                });
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+            }
+            None => Self::show_measurement_nodes(ui, state, connection, db, None, action),
+        }
+
+        if loaded && can_edit_rps && ui.small_button("+ Retention policy").clicked() {
+            *action = Some(TreeAction::RetentionPolicy {
+                connection: id,
+                action: RetentionPolicyAction::Create,
+                db: db.clone(),
+                policy: RetentionPolicy {
+                    duration: "INF".to_string(),
+                    replication: 1,
+                    ..Default::default()
+                },
+            });
+        }
+
+        if is_selected && caps.continuous_queries {
+            egui::CollapsingHeader::new(format!("Continuous queries ({})", state.continuous_queries.len()))
+                .id_salt(("continuous_queries_header", id, db))
+                .show(ui, |ui| {
+                    for cq in &state.continuous_queries {
+                        let is_open = state
+                            .cq_editor
+                            .as_ref()
+                            .and_then(|e| e.original.as_ref())
+                            .is_some_and(|o| o.name == cq.name);
+                        let response = ui.selectable_label(is_open, &cq.name).on_hover_ui(|ui| {
+                            ui.label(highlight::layout_job(ui, &cq.query, QueryLanguage::InfluxQL));
+                        });
+                        if response.clicked() {
+                            *action = Some(TreeAction::EditContinuousQuery { connection: id, db: db.clone(), cq: Some(cq.clone()) });
+                        }
+                    }
+
+                    if !connection.read_only && ui.small_button("+ Continuous query").clicked() {
+                        *action = Some(TreeAction::EditContinuousQuery { connection: id, db: db.clone(), cq: None });
+                    }
+                });
+        }
+    }
+
+    fn show_measurement_nodes(
+        ui: &mut egui::Ui,
+        state: &AppState,
+        connection: &Connection,
+        db: &String,
+        rp: Option<&String>,
+        action: &mut Option<TreeAction>,
+    ) {
+        let id = connection.id;
+        let caps = connection.capabilities();
+        let can_administer = !connection.read_only && caps.manage_databases;
+        let can_downsample = !connection.read_only && caps.select_into;
+        let db_selected = state.active_connection == Some(id) && state.selected_db.as_ref() == Some(db);
+        let Some(measurements) = connection.measurements.get(db) else {
+            return;
+        };
+
+        for measurement in measurements {
+            let is_selected = db_selected
+                && state.selected_measurement.as_ref() == Some(measurement)
+                && (rp.is_none() || state.selected_rp.as_ref() == rp);
+            let response = ui.selectable_label(is_selected, measurement);
+            let open = |new_tab| TreeAction::OpenMeasurement {
+                connection: id,
+                db: db.clone(),
+                rp: rp.cloned(),
+                measurement: measurement.clone(),
+                new_tab,
+            };
+
+            if response.clicked() {
+                *action = Some(open(false));
+            }
+            response.context_menu(|ui| {
+                if ui.button("Open in new tab").clicked() {
+                    *action = Some(open(true));
+                    ui.close_menu();
+                }
+                if can_downsample && ui.button("Downsample...").clicked() {
+                    *action = Some(TreeAction::Downsample {
+                        connection: id,
+                        db: db.clone(),
+                        measurement: measurement.clone(),
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("Gaps and anomalies...").clicked() {
+                    *action = Some(TreeAction::Gaps {
+                        connection: id,
+                        db: db.clone(),
+                        measurement: measurement.clone(),
+                    });
+                    ui.close_menu();
+                }
+                if !can_administer {
+                    return;
+                }
+                if ui.button("Drop measurement...").clicked() {
+                    *action = Some(TreeAction::Admin {
+                        connection: id,
+                        action: AdminAction::DropMeasurement {
+                            database: db.clone(),
+                            measurement: measurement.clone(),
+                        },
+                    });
+                    ui.close_menu();
+                }
+                if ui.button("Drop series...").clicked() {
+                    *action = Some(TreeAction::Admin {
+                        connection: id,
+                        action: AdminAction::DropSeries {
+                            database: db.clone(),
+                            measurement: measurement.clone(),
+                            predicate: String::new(),
+                        },
+                    });
+                    ui.close_menu();
+                }
+            });
+        }
+    }
+
+    fn apply_tree_action(&self, ctx: &egui::Context, state: &mut AppState, action: TreeAction) {
+        match action {
+            TreeAction::SelectConnection(id) => state.select_connection(id),
+            TreeAction::Reconnect(id) => self.reconnect(ctx, state, id),
+            TreeAction::ForgetCredentials(id) => self.forget_credentials(state, id),
+            TreeAction::ToggleReadOnly(id) => {
+                if let Some(connection) = state.connection_by_id(id) {
+                    connection.read_only = !connection.read_only;
+                }
+            }
+            TreeAction::Disconnect(id) => {
+                state.connections.retain(|c| c.id != id);
+                if state.active_connection == Some(id) {
+                    state.active_connection = None;
+                    if let Some(next) = state.connections.first().map(|c| c.id) {
+                        state.select_connection(next);
+                    }
+                }
+            }
+            TreeAction::OpenDatabase { connection, db, select } => {
+                if select {
+                    state.select_connection(connection);
+                    state.selected_db = Some(db.clone());
+                    state.selected_rp = None;
+                    state.continuous_queries.clear();
+                    state.attach_active_tab();
+                }
+                self.load_database(ctx, state, connection, db, select);
+            }
+            TreeAction::SelectRetentionPolicy { connection, db, rp } => {
+                let switched = state.active_connection != Some(connection) || state.selected_db.as_ref() != Some(&db);
+                state.select_connection(connection);
+                state.selected_db = Some(db.clone());
+                state.selected_rp = Some(rp);
+                state.attach_active_tab();
+                if switched {
+                    state.continuous_queries.clear();
+                    self.load_database(ctx, state, connection, db, true);
+                }
+            }
+            TreeAction::OpenMeasurement { connection, db, rp, measurement, new_tab } => {
+                let switched = state.active_connection != Some(connection) || state.selected_db.as_ref() != Some(&db);
+                state.select_connection(connection);
+                state.selected_db = Some(db.clone());
+                if rp.is_some() {
+                    state.selected_rp = rp;
+                }
+                if switched {
+                    state.continuous_queries.clear();
+                    self.load_database(ctx, state, connection, db, true);
+                }
+                self.open_measurement(ctx, state, measurement, new_tab);
+            }
+            TreeAction::Cardinality { connection, db } => {
+                state.select_connection(connection);
+                state.cardinality = Some(CardinalityView::new(connection, db.clone()));
+                if let Some(client) = state.client() {
+                    let state_clone = Arc::clone(&self.state);
+                    let ctx_clone = ctx.clone();
+
+                    self.runtime.spawn(async move {
+                        Self::load_cardinality(state_clone, ctx_clone, client, db, false).await;
+                    });
+                }
+            }
+            TreeAction::Admin { connection, action } => {
+                state.select_connection(connection);
+                state.admin_dialog = Some(AdminDialog {
+                    connection,
+                    action,
+                    confirm: String::new(),
+                });
+            }
+            TreeAction::RetentionPolicy { connection, action, db, policy } => {
+                state.select_connection(connection);
+                state.rp_dialog = Some(RetentionPolicyDialog {
+                    connection,
+                    action,
+                    database: db,
+                    policy,
+                    confirm: String::new(),
+                });
+            }
+            TreeAction::Downsample { connection, db, measurement } => {
+                state.select_connection(connection);
+                self.open_downsample(ctx, state, db, measurement);
+            }
+            TreeAction::Gaps { connection, db, measurement } => {
+                state.select_connection(connection);
+                self.open_gaps(ctx, state, db, measurement);
+            }
+            TreeAction::EditContinuousQuery { connection, db, cq } => {
+                state.cq_editor = Some(CqEditor::new(connection, db, cq));
+            }
+        }
+    }
+
+    /// Lists the retention policies and measurements of `db`, and its continuous queries when it is the selected one.
+    pub(crate) fn load_database(&self, ctx: &egui::Context, state: &mut AppState, id: u64, db: String, selected: bool) {
+        let Some(connection) = state.connection_by_id(id) else {
+            return;
+        };
+        let caps = connection.capabilities();
+        let client = connection.client.clone();
+        connection.loading.insert(db.clone());
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            if caps.retention_policies {
+                Self::load_retention_policies(Arc::clone(&state_clone), ctx_clone.clone(), id, client.clone(), db.clone()).await;
+            }
+            if selected && caps.continuous_queries {
+                Self::load_continuous_queries(Arc::clone(&state_clone), ctx_clone.clone(), id, client.clone(), db.clone()).await;
+            }
+
+            let result = client.show_measurements(&db).await;
+            {
+                let mut state = state_clone.lock().unwrap();
+                match result {
+                    Ok(measurements) => {
+                        if selected {
+                            state.status = format!("{} measurements", measurements.len());
+                        }
+                        if let Some(connection) = state.connection_by_id(id) {
+                            connection.measurements.insert(db.clone(), measurements);
+                        }
+                    }
+                    Err(e) => state.status = format!("Error: {}", e),
+                }
+                if let Some(connection) = state.connection_by_id(id) {
+                    connection.loading.remove(&db);
+                    // Keep the node from retrying every frame after an error
+                    connection.measurements.entry(db).or_default();
+                }
+            }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    pub(crate) async fn load_retention_policies(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        id: u64,
+        client: InfluxClient,
+        db: String,
+    ) {
+        match client.show_retention_policies(&db).await {
+            Ok(policies) => {
+                let mut state = state.lock().unwrap();
+                // The user may have moved on to another database meanwhile
+                if state.active_connection == Some(id) && state.selected_db.as_deref() == Some(db.as_str()) {
+                    let still_exists = state
+                        .selected_rp
+                        .as_ref()
+                        .map(|rp| policies.iter().any(|p| &p.name == rp))
+                        .unwrap_or(false);
+                    if !still_exists {
+                        state.selected_rp = policies.iter().find(|p| p.default).map(|p| p.name.clone());
+                    }
+                }
+                if let Some(connection) = state.connection_by_id(id) {
+                    connection.retention_policies.insert(db, policies);
+                }
+            }
+            Err(e) => {
+                let mut state = state.lock().unwrap();
+                state.status = format!("Error: {}", e);
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    pub(crate) async fn refresh_databases(state: Arc<Mutex<AppState>>, id: u64, client: &InfluxClient) {
+        if let Ok(dbs) = client.show_databases().await {
+            let mut state = state.lock().unwrap();
+            if state.active_connection == Some(id) && state.selected_db.as_ref().map(|db| !dbs.contains(db)).unwrap_or(false) {
+                state.selected_db = None;
+                state.selected_rp = None;
+                state.selected_measurement = None;
+            }
+            if let Some(connection) = state.connection_by_id(id) {
+                connection.retention_policies.retain(|db, _| dbs.contains(db));
+                connection.measurements.retain(|db, _| dbs.contains(db));
+                connection.databases = dbs;
+            }
+        }
+    }
+
+    pub(crate) async fn refresh_measurements(state: Arc<Mutex<AppState>>, id: u64, client: &InfluxClient, db: &str) {
+        if let Ok(measurements) = client.show_measurements(db).await {
+            let mut state = state.lock().unwrap();
+            if state.active_connection == Some(id)
+                && state.selected_db.as_deref() == Some(db)
+                && state.selected_measurement.as_ref().map(|m| !measurements.contains(m)).unwrap_or(false)
+            {
+                state.selected_measurement = None;
+            }
+            if let Some(connection) = state.connection_by_id(id) {
+                connection.measurements.insert(db.to_string(), measurements);
+            }
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use eframe::egui;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+use crate::highlight;
+use crate::influx::{self, ContinuousQuery, InfluxClient};
+use crate::ui::{value_to_string, AppState, QueryLanguage};
+use crate::InfluxDBApp;
+
+// Rows shown for a test run
+const TEST_ROWS: usize = 100;
+
+/// The parts of a `CREATE CONTINUOUS QUERY` statement the editor works with.
+#[derive(Debug, Clone, Default)]
+pub struct CqDefinition {
+    pub every: String,
+    pub for_duration: String,
+    pub select: String,
+}
+
+impl CqDefinition {
+    /// Splits a definition as returned by `SHOW CONTINUOUS QUERIES`.
+    pub fn parse(query: &str) -> Self {
+        let upper = query.to_ascii_uppercase();
+        let (Some(begin), Some(end)) = (upper.find(" BEGIN "), upper.rfind("END")) else {
+            return Self {
+                select: query.to_string(),
+                ..Default::default()
+            };
+        };
+        let select = query[begin + " BEGIN ".len()..end.max(begin + " BEGIN ".len())].trim().to_string();
+
+        let mut definition = Self {
+            select,
+            ..Default::default()
+        };
+        if let Some(resample) = upper[..begin].find(" RESAMPLE ") {
+            let clause: Vec<&str> = query[resample + " RESAMPLE ".len()..begin].split_whitespace().collect();
+            for pair in clause.chunks(2) {
+                match (pair[0].to_ascii_uppercase().as_str(), pair.get(1)) {
+                    ("EVERY", Some(d)) => definition.every = d.to_string(),
+                    ("FOR", Some(d)) => definition.for_duration = d.to_string(),
+                    _ => {}
+                }
+            }
+        }
+        definition
+    }
+}
+
+pub struct CqEditor {
+    /// Connection the editor was opened on
+    pub connection: u64,
+    pub database: String,
+    pub original: Option<ContinuousQuery>,
+    pub name: String,
+    pub definition: CqDefinition,
+    pub test_window: String,
+    pub test_result: Option<(Vec<String>, Vec<Vec<Value>>)>,
+    pub confirm_drop: String,
+    pub busy: bool,
+    pub error: Option<String>,
+}
+
+impl CqEditor {
+    pub fn new(connection: u64, database: String, original: Option<ContinuousQuery>) -> Self {
+        let (name, definition) = match &original {
+            Some(cq) => (cq.name.clone(), CqDefinition::parse(&cq.query)),
+            None => (
+                String::new(),
+                CqDefinition {
+                    select: "SELECT mean(*) INTO \"downsampled\" FROM \"measurement\" GROUP BY time(1h), *".to_string(),
+                    ..Default::default()
+                },
+            ),
+        };
+        Self {
+            connection,
+            database,
+            original,
+            name,
+            definition,
+            test_window: "1h".to_string(),
+            test_result: None,
+            confirm_drop: String::new(),
+            busy: false,
+            error: None,
+        }
+    }
+
+    /// The full `CREATE CONTINUOUS QUERY` statement, or why it would be rejected.
+    pub fn statement(&self) -> Result<String, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Name is required".to_string());
+        }
+
+        let select = self.definition.select.trim();
+        let upper = select.to_ascii_uppercase();
+        if !upper.starts_with("SELECT ") {
+            return Err("The body must be a SELECT statement".to_string());
+        }
+        if !upper.contains(" INTO ") {
+            return Err("The SELECT needs an INTO clause".to_string());
+        }
+        let interval = group_by_interval(select).ok_or("The SELECT needs GROUP BY time(<interval>)")?;
+
+        let duration = |label: &str, value: &str| -> Result<Option<i64>, String> {
+            match value.trim() {
+                "" => Ok(None),
+                v => influx::parse_duration(v)
+                    .filter(|d| *d > 0)
+                    .map(Some)
+                    .ok_or_else(|| format!("Invalid {} duration '{}'", label, v)),
+            }
+        };
+        let every = duration("EVERY", &self.definition.every)?;
+        let for_duration = duration("FOR", &self.definition.for_duration)?;
+
+        // The same checks the server makes, so the CQ isn't dropped before a recreate that would fail
+        if let Some(f) = for_duration {
+            if f < interval {
+                return Err("FOR must be at least the GROUP BY time interval".to_string());
+            }
+            if every.is_some_and(|e| f < e) {
+                return Err("FOR must be at least EVERY".to_string());
+            }
+        }
+
+        let mut statement = format!(
+            "CREATE CONTINUOUS QUERY {} ON {}",
+            influx::quote_ident(name),
+            influx::quote_ident(&self.database)
+        );
+        if every.is_some() || for_duration.is_some() {
+            statement.push_str(" RESAMPLE");
+            if every.is_some() {
+                statement.push_str(&format!(" EVERY {}", self.definition.every.trim()));
+            }
+            if for_duration.is_some() {
+                statement.push_str(&format!(" FOR {}", self.definition.for_duration.trim()));
+            }
+        }
+        statement.push_str(&format!(" BEGIN {} END", select));
+        Ok(statement)
+    }
+
+    /// The inner SELECT without its INTO clause, restricted to the last `test_window`.
+    fn test_query(&self) -> Result<String, String> {
+        let window = self.test_window.trim();
+        influx::parse_duration(window).ok_or_else(|| format!("Invalid test window '{}'", window))?;
+
+        let select = self.definition.select.trim();
+        let upper = select.to_ascii_uppercase();
+        let into = upper.find(" INTO ").ok_or("The SELECT needs an INTO clause")?;
+        let from = upper[into..].find(" FROM ").map(|i| i + into).ok_or("The SELECT needs a FROM clause")?;
+        let select = format!("{}{}", &select[..into], &select[from..]);
+
+        let upper = select.to_ascii_uppercase();
+        let group_by = upper.find(" GROUP BY ").unwrap_or(select.len());
+        let time = format!("time > now() - {}", window);
+        let query = match upper[..group_by].find(" WHERE ") {
+            Some(w) => format!(
+                "{} WHERE ({}) AND {}{}",
+                &select[..w],
+                select[w + " WHERE ".len()..group_by].trim(),
+                time,
+                &select[group_by..]
+            ),
+            None => format!("{} WHERE {}{}", &select[..group_by], time, &select[group_by..]),
+        };
+        Ok(query)
+    }
+}
+
+/// Nanoseconds of the `GROUP BY time(...)` interval.
+fn group_by_interval(select: &str) -> Option<i64> {
+    let upper = select.to_ascii_uppercase();
+    let group_by = upper.find("GROUP BY")?;
+    let time = upper[group_by..].find("TIME(")? + group_by + "TIME(".len();
+    let close = select[time..].find([')', ','])? + time;
+    influx::parse_duration(&select[time..close])
+}
+
+enum CqAction {
+    Save(String),
+    Drop,
+    Test(String),
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_cq_editor(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.cq_editor.as_ref().map(|e| e.connection) else {
+            return;
+        };
+        let Some(connection) = state.connection_of(id) else {
+            state.cq_editor = None;
+            return;
+        };
+        let client = connection.client.clone();
+        let read_only = connection.read_only;
+        let Some(editor) = &mut state.cq_editor else {
+            return;
+        };
+
+        let title = match &editor.original {
+            Some(cq) => format!("Continuous query: {}", cq.name),
+            None => "New continuous query".to_string(),
+        };
+        let mut open = true;
+        let mut action = None;
+
+        egui::Window::new(title)
+            .id(egui::Id::new("cq_editor"))
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                let statement = editor.statement();
+
+                ui.add_enabled_ui(!editor.busy && !read_only, |ui| {
+                    egui::Grid::new("cq_form").num_columns(2).show(ui, |ui| {
+                        ui.label("Database:");
+                        ui.label(format!("{} on {}", editor.database, client.host()));
+                        ui.end_row();
+
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut editor.name);
+                        ui.end_row();
+
+                        ui.label("RESAMPLE EVERY:");
+                        ui.add(egui::TextEdit::singleline(&mut editor.definition.every).hint_text("GROUP BY interval"));
+                        ui.end_row();
+
+                        ui.label("RESAMPLE FOR:");
+                        ui.add(egui::TextEdit::singleline(&mut editor.definition.for_duration).hint_text("GROUP BY interval"));
+                        ui.end_row();
+                    });
+
+                    let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                        let mut job = highlight::layout_job(ui, text, QueryLanguage::InfluxQL);
+                        job.wrap.max_width = wrap_width;
+                        ui.fonts(|f| f.layout_job(job))
+                    };
+                    ui.add(
+                        egui::TextEdit::multiline(&mut editor.definition.select)
+                            .code_editor()
+                            .desired_rows(4)
+                            .desired_width(f32::INFINITY)
+                            .layouter(&mut layouter),
+                    );
+                });
+
+                match &statement {
+                    Ok(statement) => {
+                        let mut job = highlight::layout_job(ui, statement, QueryLanguage::InfluxQL);
+                        job.wrap.max_width = ui.available_width();
+                        ui.label(job);
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, e);
+                    }
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if !read_only {
+                        let label = if editor.original.is_some() { "Save (drop + recreate)" } else { "Create" };
+                        if ui.add_enabled(!editor.busy && statement.is_ok(), egui::Button::new(label)).clicked() {
+                            if let Ok(statement) = &statement {
+                                action = Some(CqAction::Save(statement.clone()));
+                            }
+                        }
+                    }
+
+                    ui.label("Test over last:");
+                    ui.add(egui::TextEdit::singleline(&mut editor.test_window).desired_width(50.0));
+                    if ui.add_enabled(!editor.busy, egui::Button::new("Test run")).clicked() {
+                        match editor.test_query() {
+                            Ok(query) => action = Some(CqAction::Test(query)),
+                            Err(e) => editor.error = Some(e),
+                        }
+                    }
+                    if editor.busy {
+                        ui.spinner();
+                    }
+                });
+
+                if let (false, Some(original)) = (read_only, &editor.original) {
+                    ui.horizontal(|ui| {
+                        ui.label("Type the name to drop:");
+                        ui.add(egui::TextEdit::singleline(&mut editor.confirm_drop).desired_width(150.0));
+                        let confirmed = editor.confirm_drop == original.name;
+                        if ui.add_enabled(confirmed && !editor.busy, egui::Button::new("Drop")).clicked() {
+                            action = Some(CqAction::Drop);
+                        }
+                    });
+                }
+
+                if let Some(err) = &editor.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                if let Some((columns, rows)) = &editor.test_result {
+                    ui.separator();
+                    ui.label(format!("{} rows", rows.len()));
+                    egui::ScrollArea::both().id_salt("cq_test_result").show(ui, |ui| {
+                        egui::Grid::new("cq_test_grid").striped(true).show(ui, |ui| {
+                            for column in columns {
+                                ui.strong(column);
+                            }
+                            ui.end_row();
+                            for row in rows.iter().take(TEST_ROWS) {
+                                for value in row {
+                                    ui.label(value_to_string(value));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+            });
+
+        if !open {
+            state.cq_editor = None;
+            return;
+        }
+
+        let Some(action) = action else {
+            return;
+        };
+
+        editor.busy = true;
+        editor.error = None;
+        let connection = editor.connection;
+        let database = editor.database.clone();
+        let original = editor.original.clone();
+        let name = editor.name.trim().to_string();
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            match action {
+                CqAction::Test(query) => {
+                    let result = client.query(&query, Some(&database)).await;
+                    let mut state = state_clone.lock().unwrap();
+                    if let Some(editor) = &mut state.cq_editor {
+                        editor.busy = false;
+                        match result {
+                            Ok(result) => editor.test_result = Some(result.unwrap_or_default()),
+                            Err(e) => editor.error = Some(format!("Error: {}", e)),
+                        }
+                    }
+                }
+                CqAction::Save(statement) => {
+                    let result = Self::save_continuous_query(&client, &database, original.as_ref(), &statement).await;
+                    {
+                        let mut state = state_clone.lock().unwrap();
+                        if let Some(editor) = &mut state.cq_editor {
+                            editor.busy = false;
+                            match &result {
+                                Ok(()) => {
+                                    editor.original = Some(ContinuousQuery {
+                                        name: name.clone(),
+                                        query: statement.clone(),
+                                    });
+                                    editor.confirm_drop.clear();
+                                }
+                                Err(e) => editor.error = Some(format!("Error: {}", e)),
+                            }
+                        }
+                        if result.is_ok() {
+                            state.status = format!("Saved continuous query {}", name);
+                        }
+                    }
+                    Self::load_continuous_queries(Arc::clone(&state_clone), ctx_clone.clone(), connection, client, database).await;
+                }
+                CqAction::Drop => {
+                    let original = original.unwrap_or_default();
+                    let result = client.drop_continuous_query(&database, &original.name).await;
+                    {
+                        let mut state = state_clone.lock().unwrap();
+                        match result {
+                            Ok(()) => {
+                                state.cq_editor = None;
+                                state.status = format!("Dropped continuous query {}", original.name);
+                            }
+                            Err(e) => {
+                                if let Some(editor) = &mut state.cq_editor {
+                                    editor.busy = false;
+                                    editor.error = Some(format!("Error: {}", e));
+                                }
+                            }
+                        }
+                    }
+                    Self::load_continuous_queries(Arc::clone(&state_clone), ctx_clone.clone(), connection, client, database).await;
+                }
+            }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    /// CQs can't be altered, so an edit drops the old one first and puts it back if the new one is rejected.
+    async fn save_continuous_query(
+        client: &InfluxClient,
+        database: &str,
+        original: Option<&ContinuousQuery>,
+        statement: &str,
+    ) -> anyhow::Result<()> {
+        if let Some(original) = original {
+            client.drop_continuous_query(database, &original.name).await?;
+        }
+
+        match client.execute(statement, None).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let Some(original) = original {
+                    client.execute(&original.query, None).await?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) async fn load_continuous_queries(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        connection: u64,
+        client: InfluxClient,
+        db: String,
+    ) {
+        let result = client.show_continuous_queries(&db).await;
+
+        {
+            let mut state = state.lock().unwrap();
+            match result {
+                Ok(queries) => {
+                    // The user may have moved on to another connection or database meanwhile
+                    if state.active_connection == Some(connection) && state.selected_db.as_deref() == Some(db.as_str()) {
+                        state.continuous_queries = queries;
+                    }
+                }
+                Err(e) => state.status = format!("Error: {}", e),
+            }
+        }
+        ctx.request_repaint();
+    }
+}
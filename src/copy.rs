@@ -0,0 +1,459 @@
+use anyhow::{anyhow, Result};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::connections::client_for;
+use crate::influx::{self, InfluxClient};
+use crate::line_protocol;
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+/// What to copy where. Saved with the checkpoint so an interrupted copy can be resumed as-is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CopySpec {
+    pub source_host: String,
+    pub source_db: String,
+    pub source_rp: String,
+    pub measurement: String,
+    /// RFC3339, empty for "from the first point"
+    pub start: String,
+    /// RFC3339, empty for "until now"
+    pub end: String,
+    pub tag_filter: String,
+    pub target_host: String,
+    pub target_db: String,
+    pub target_rp: String,
+    pub target_measurement: String,
+    pub window_minutes: u64,
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyCheckpoint {
+    pub spec: CopySpec,
+    pub range_start: i64,
+    pub range_end: i64,
+    pub window_start: i64,
+    pub offset: usize,
+    pub copied: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStatus {
+    Idle,
+    Running,
+    Paused,
+    Cancelling,
+    Done,
+    Failed,
+}
+
+pub struct CopyView {
+    pub spec: CopySpec,
+    pub status: CopyStatus,
+    pub message: String,
+    pub copied: u64,
+    pub points_per_sec: f64,
+    pub progress: f32,
+    pub saved_checkpoint: Option<CopyCheckpoint>,
+}
+
+impl CopyView {
+    pub fn new(host: &str, db: Option<&str>, rp: Option<&str>, measurement: Option<&str>) -> Self {
+        let measurement = measurement.unwrap_or_default().to_string();
+        Self {
+            spec: CopySpec {
+                source_host: host.to_string(),
+                source_db: db.unwrap_or_default().to_string(),
+                source_rp: rp.unwrap_or_default().to_string(),
+                target_host: host.to_string(),
+                target_measurement: measurement.clone(),
+                measurement,
+                window_minutes: 60,
+                batch_size: 10000,
+                ..Default::default()
+            },
+            status: CopyStatus::Idle,
+            message: String::new(),
+            copied: 0,
+            points_per_sec: 0.0,
+            progress: 0.0,
+            saved_checkpoint: load_checkpoint(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(self.status, CopyStatus::Running | CopyStatus::Paused | CopyStatus::Cancelling)
+    }
+}
+
+fn checkpoint_path() -> Option<PathBuf> {
+    eframe::storage_dir(crate::APP_ID).map(|dir| dir.join("copy_checkpoint.json"))
+}
+
+fn load_checkpoint() -> Option<CopyCheckpoint> {
+    let data = std::fs::read_to_string(checkpoint_path()?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn save_checkpoint(checkpoint: &CopyCheckpoint) -> Result<()> {
+    let path = checkpoint_path().ok_or_else(|| anyhow!("No storage directory"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(checkpoint)?)?;
+    Ok(())
+}
+
+fn clear_checkpoint() {
+    if let Some(path) = checkpoint_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+pub(crate) fn parse_time(s: &str) -> Result<Option<i64>> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    let t = chrono::DateTime::parse_from_rfc3339(s.trim())
+        .map_err(|e| anyhow!("Invalid time '{}': {}", s, e))?;
+    Ok(t.timestamp_nanos_opt())
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    Some(s.trim()).filter(|s| !s.is_empty())
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_copy_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let proxy = (!state.proxy.is_empty()).then(|| state.proxy.clone());
+        let Some(view) = &mut state.copy_job else {
+            return;
+        };
+
+        let mut open = true;
+        let mut start: Option<Option<CopyCheckpoint>> = None;
+
+        egui::Window::new("Copy measurement data")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let editable = !view.is_active();
+                let spec = &mut view.spec;
+
+                ui.add_enabled_ui(editable, |ui| {
+                    egui::Grid::new("copy_form").num_columns(4).show(ui, |ui| {
+                        ui.strong("Source");
+                        ui.label("");
+                        ui.strong("Target");
+                        ui.label("");
+                        ui.end_row();
+
+                        ui.label("Host:");
+                        ui.text_edit_singleline(&mut spec.source_host);
+                        ui.label("Host:");
+                        ui.text_edit_singleline(&mut spec.target_host);
+                        ui.end_row();
+
+                        ui.label("Database:");
+                        ui.text_edit_singleline(&mut spec.source_db);
+                        ui.label("Database:");
+                        ui.text_edit_singleline(&mut spec.target_db);
+                        ui.end_row();
+
+                        ui.label("RP:");
+                        ui.text_edit_singleline(&mut spec.source_rp);
+                        ui.label("RP:");
+                        ui.text_edit_singleline(&mut spec.target_rp);
+                        ui.end_row();
+
+                        ui.label("Measurement:");
+                        ui.text_edit_singleline(&mut spec.measurement);
+                        ui.label("Measurement:");
+                        ui.text_edit_singleline(&mut spec.target_measurement);
+                        ui.end_row();
+                    });
+
+                    ui.separator();
+
+                    egui::Grid::new("copy_range").num_columns(2).show(ui, |ui| {
+                        ui.label("From:");
+                        ui.add(egui::TextEdit::singleline(&mut spec.start).hint_text("first point (RFC3339)"));
+                        ui.end_row();
+
+                        ui.label("To:");
+                        ui.add(egui::TextEdit::singleline(&mut spec.end).hint_text("now (RFC3339)"));
+                        ui.end_row();
+
+                        ui.label("Tag filter:");
+                        ui.add(egui::TextEdit::singleline(&mut spec.tag_filter).hint_text("\"host\" = 'server01'"));
+                        ui.end_row();
+
+                        ui.label("Window (minutes):");
+                        ui.add(egui::DragValue::new(&mut spec.window_minutes).range(1..=525_600));
+                        ui.end_row();
+
+                        ui.label("Batch size:");
+                        ui.add(egui::DragValue::new(&mut spec.batch_size).range(100..=100_000));
+                        ui.end_row();
+                    });
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    match view.status {
+                        CopyStatus::Running => {
+                            if ui.button("Pause").clicked() {
+                                view.status = CopyStatus::Paused;
+                            }
+                        }
+                        CopyStatus::Paused => {
+                            if ui.button("Resume").clicked() {
+                                view.status = CopyStatus::Running;
+                            }
+                        }
+                        _ => {
+                            let ready = !view.spec.source_db.is_empty()
+                                && !view.spec.measurement.is_empty()
+                                && !view.spec.target_db.is_empty();
+                            if ui.add_enabled(ready, egui::Button::new("Start")).clicked() {
+                                start = Some(None);
+                            }
+                            if let Some(checkpoint) = &view.saved_checkpoint {
+                                let label = format!(
+                                    "Continue {} -> {} ({} copied)",
+                                    checkpoint.spec.measurement, checkpoint.spec.target_db, checkpoint.copied
+                                );
+                                if ui.button(label).clicked() {
+                                    view.spec = checkpoint.spec.clone();
+                                    start = Some(Some(checkpoint.clone()));
+                                }
+                            }
+                        }
+                    }
+
+                    if matches!(view.status, CopyStatus::Running | CopyStatus::Paused) && ui.button("Stop").clicked() {
+                        view.status = CopyStatus::Cancelling;
+                    }
+                });
+
+                if view.status != CopyStatus::Idle {
+                    ui.add(egui::ProgressBar::new(view.progress).show_percentage());
+                    ui.label(format!(
+                        "{} points copied, {:.0} points/s",
+                        view.copied, view.points_per_sec
+                    ));
+                }
+                if !view.message.is_empty() {
+                    ui.label(&view.message);
+                }
+            });
+
+        if !open && !view.is_active() {
+            state.copy_job = None;
+            return;
+        }
+
+        // Reading from a read-only server is fine, writing into one isn't
+        let target_read_only = state.connections.iter().any(|c| c.host == view.spec.target_host && c.read_only);
+        if start.is_some() && target_read_only {
+            view.status = CopyStatus::Failed;
+            view.message = format!("Error: {} is read-only", view.spec.target_host);
+        } else if let Some(checkpoint) = start {
+            view.status = CopyStatus::Running;
+            view.message = "Starting...".to_string();
+            view.copied = checkpoint.as_ref().map(|c| c.copied).unwrap_or(0);
+            view.progress = 0.0;
+
+            let spec = view.spec.clone();
+            let source = client_for(&state.connections, spec.source_host.clone(), proxy.clone());
+            let target = client_for(&state.connections, spec.target_host.clone(), proxy);
+            let state_clone = Arc::clone(&self.state);
+            let ctx_clone = ctx.clone();
+
+            self.runtime.spawn(async move {
+                let result = Self::run_copy(Arc::clone(&state_clone), ctx_clone.clone(), source, target, spec, checkpoint).await;
+
+                let mut state = state_clone.lock().unwrap();
+                if let Some(view) = &mut state.copy_job {
+                    match result {
+                        Ok(true) => {
+                            view.status = CopyStatus::Done;
+                            view.progress = 1.0;
+                            view.message = "Copy complete".to_string();
+                            view.saved_checkpoint = None;
+                        }
+                        Ok(false) => {
+                            view.status = CopyStatus::Idle;
+                            view.message = "Stopped, progress saved".to_string();
+                            view.saved_checkpoint = load_checkpoint();
+                        }
+                        Err(e) => {
+                            view.status = CopyStatus::Failed;
+                            view.message = format!("Error: {} (progress saved)", e);
+                            view.saved_checkpoint = load_checkpoint();
+                        }
+                    }
+                }
+                ctx_clone.request_repaint();
+            });
+        }
+    }
+
+    /// Copies window by window; returns `Ok(false)` when stopped by the user.
+    async fn run_copy(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        source: InfluxClient,
+        target: InfluxClient,
+        spec: CopySpec,
+        checkpoint: Option<CopyCheckpoint>,
+    ) -> Result<bool> {
+        let source_rp = non_empty(&spec.source_rp);
+        let target_rp = non_empty(&spec.target_rp);
+        let target_measurement = non_empty(&spec.target_measurement).unwrap_or(&spec.measurement);
+        let from = influx::qualified_measurement(&spec.source_db, source_rp, &spec.measurement);
+
+        // SELECT * doesn't say which columns are tags, so ask the schema
+        let tag_keys = source.show_tag_keys(&spec.source_db, &spec.measurement).await?;
+        let field_types: HashMap<String, String> = source
+            .show_field_keys(&spec.source_db, source_rp, &spec.measurement)
+            .await?
+            .into_iter()
+            .rev()
+            .collect();
+
+        let mut checkpoint = match checkpoint {
+            Some(c) => c,
+            None => {
+                let range_start = match parse_time(&spec.start)? {
+                    Some(t) => t,
+                    None => source
+                        .first_timestamp(&spec.source_db, source_rp, &spec.measurement)
+                        .await?
+                        .ok_or_else(|| anyhow!("Source measurement has no data"))?,
+                };
+                let range_end = match parse_time(&spec.end)? {
+                    Some(t) => t,
+                    None => chrono::Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX),
+                };
+                CopyCheckpoint {
+                    spec: spec.clone(),
+                    range_start,
+                    range_end,
+                    window_start: range_start,
+                    offset: 0,
+                    copied: 0,
+                }
+            }
+        };
+
+        let window = spec.window_minutes.max(1) as i64 * 60 * 1_000_000_000;
+        let filter = non_empty(&spec.tag_filter).map(|f| format!(" AND ({})", f)).unwrap_or_default();
+        let started = Instant::now();
+        let copied_at_start = checkpoint.copied;
+
+        while checkpoint.window_start <= checkpoint.range_end {
+            // Honour pause/stop between batches
+            loop {
+                let status = {
+                    let state = state.lock().unwrap();
+                    state.copy_job.as_ref().map(|v| v.status)
+                };
+                match status {
+                    Some(CopyStatus::Paused) => tokio::time::sleep(Duration::from_millis(250)).await,
+                    Some(CopyStatus::Running) => break,
+                    _ => {
+                        save_checkpoint(&checkpoint)?;
+                        return Ok(false);
+                    }
+                }
+            }
+
+            let window_end = checkpoint.window_start.saturating_add(window).min(checkpoint.range_end + 1);
+            let query = format!(
+                "SELECT * FROM {} WHERE time >= {} AND time < {}{} LIMIT {} OFFSET {}",
+                from, checkpoint.window_start, window_end, filter, spec.batch_size, checkpoint.offset
+            );
+            influx::record_issued(&query);
+
+            let (columns, rows) = source
+                .query_epoch(&query, Some(&spec.source_db), "ns")
+                .await?
+                .unwrap_or_default();
+
+            if !rows.is_empty() {
+                let lines = encode_rows(target_measurement, &columns, &rows, &tag_keys, &field_types);
+                if !lines.is_empty() {
+                    target.write(&spec.target_db, target_rp, lines.join("\n")).await?;
+                }
+                checkpoint.copied += lines.len() as u64;
+            }
+
+            if rows.len() < spec.batch_size {
+                checkpoint.window_start = window_end;
+                checkpoint.offset = 0;
+            } else {
+                checkpoint.offset += rows.len();
+            }
+            save_checkpoint(&checkpoint)?;
+
+            {
+                let mut state = state.lock().unwrap();
+                if let Some(view) = &mut state.copy_job {
+                    let span = (checkpoint.range_end - checkpoint.range_start).max(1) as f64;
+                    let done = (checkpoint.window_start - checkpoint.range_start) as f64;
+                    view.progress = (done / span).clamp(0.0, 1.0) as f32;
+                    view.copied = checkpoint.copied;
+                    view.points_per_sec =
+                        (checkpoint.copied - copied_at_start) as f64 / started.elapsed().as_secs_f64().max(0.001);
+                    view.message = format!(
+                        "At {}",
+                        chrono::DateTime::from_timestamp_nanos(checkpoint.window_start).to_rfc3339()
+                    );
+                }
+            }
+            ctx.request_repaint();
+        }
+
+        clear_checkpoint();
+        Ok(true)
+    }
+}
+
+fn encode_rows(
+    measurement: &str,
+    columns: &[String],
+    rows: &[Vec<Value>],
+    tag_keys: &[String],
+    field_types: &HashMap<String, String>,
+) -> Vec<String> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut timestamp = None;
+            let mut tags = Vec::new();
+            let mut fields = Vec::new();
+
+            for (column, value) in columns.iter().zip(row) {
+                if column == "time" {
+                    timestamp = value.as_i64();
+                } else if tag_keys.contains(column) {
+                    if let Some(v) = value.as_str() {
+                        tags.push((column.clone(), v.to_string()));
+                    }
+                } else if let Some(v) = line_protocol::field_value(value, field_types.get(column).map(String::as_str)) {
+                    fields.push((column.clone(), v));
+                }
+            }
+
+            tags.sort();
+            line_protocol::line(measurement, &tags, &fields, timestamp?)
+        })
+        .collect()
+}
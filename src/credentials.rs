@@ -0,0 +1,577 @@
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+const VAULT_FILE: &str = "credentials.vault";
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// What a connection authenticates with. Only ever kept in memory, the keyring or the vault.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Credentials {
+    /// InfluxDB 1.x users, sent as basic auth
+    Password { username: String, password: String },
+    /// InfluxDB 2.x/3.x API tokens
+    Token(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CredentialStore {
+    Keyring,
+    Vault,
+}
+
+impl CredentialStore {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CredentialStore::Keyring => "OS keyring",
+            CredentialStore::Vault => "local vault",
+        }
+    }
+}
+
+/// Where a connection's credentials were saved; this is all the session file holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialRef {
+    pub store: CredentialStore,
+    pub key: String,
+}
+
+/// Saved credentials by key, as stored encrypted in the vault file.
+pub type Vault = BTreeMap<String, Credentials>;
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts the vault with a key derived from `passphrase`; salt and nonce are taken as given.
+pub fn seal_with(vault: &Vault, passphrase: &str, salt: &[u8], nonce: &[u8]) -> Result<String> {
+    if nonce.len() != 12 {
+        return Err(anyhow!("Nonce must be 12 bytes"));
+    }
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    let plaintext = serde_json::to_vec(vault)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(nonce), plaintext.as_slice())
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    Ok(serde_json::to_string_pretty(&VaultFile {
+        version: VAULT_VERSION,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })?)
+}
+
+/// Like `seal_with`, with a fresh random salt and nonce.
+pub fn seal(vault: &Vault, passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    seal_with(vault, passphrase, &salt, &nonce)
+}
+
+/// Decrypts a vault written by `seal`.
+pub fn open(text: &str, passphrase: &str) -> Result<Vault> {
+    let file: VaultFile = serde_json::from_str(text).context("Not a credential vault")?;
+    if file.version != VAULT_VERSION {
+        return Err(anyhow!("Unsupported vault version {}", file.version));
+    }
+    let salt = STANDARD.decode(&file.salt)?;
+    let nonce = STANDARD.decode(&file.nonce)?;
+    let ciphertext = STANDARD.decode(&file.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err(anyhow!("Damaged vault"));
+    }
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow!("Wrong passphrase or damaged vault"))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// The vault file; a missing file is an empty vault that the first passphrase written with creates.
+pub struct VaultStore {
+    path: PathBuf,
+}
+
+impl VaultStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The vault in the app's storage directory.
+    pub fn local() -> Result<Self> {
+        eframe::storage_dir(crate::APP_ID)
+            .map(|dir| Self::new(dir.join(VAULT_FILE)))
+            .ok_or_else(|| anyhow!("No storage directory for the vault"))
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn read(&self, passphrase: &str) -> Result<Vault> {
+        if !self.exists() {
+            return Ok(Vault::new());
+        }
+        open(&std::fs::read_to_string(&self.path)?, passphrase)
+    }
+
+    /// Replaces the file through a rename, so a crash mid-write leaves the previous vault intact.
+    pub fn write(&self, vault: &Vault, passphrase: &str) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp = self.path.with_extension("vault.tmp");
+        std::fs::write(&temp, seal(vault, passphrase)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn store(&self, key: &str, credentials: &Credentials, passphrase: &str) -> Result<()> {
+        let mut vault = self.read(passphrase)?;
+        vault.insert(key.to_string(), credentials.clone());
+        self.write(&vault, passphrase)
+    }
+
+    pub fn load(&self, key: &str, passphrase: &str) -> Result<Credentials> {
+        self.read(passphrase)?
+            .remove(key)
+            .ok_or_else(|| anyhow!("No credentials for {} in the vault", key))
+    }
+
+    pub fn forget(&self, key: &str, passphrase: &str) -> Result<()> {
+        let mut vault = self.read(passphrase)?;
+        if vault.remove(key).is_some() {
+            self.write(&vault, passphrase)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key a connection's credentials are saved under: `user@host`, or a token fingerprint for tokens,
+/// so connections to one host with different logins don't overwrite each other.
+pub fn credential_key(host: &str, credentials: &Credentials) -> String {
+    match credentials {
+        Credentials::Password { username, .. } => format!("{}@{}", username, host),
+        Credentials::Token(token) => {
+            let mut hasher = DefaultHasher::new();
+            token.hash(&mut hasher);
+            format!("token-{:08x}@{}", hasher.finish() as u32, host)
+        }
+    }
+}
+
+fn keyring_entry(key: &str) -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(crate::APP_ID, key)?)
+}
+
+/// Saves to the OS keyring, or to the vault when there is none and a passphrase is given.
+pub fn store(key: &str, credentials: &Credentials, passphrase: Option<&str>) -> Result<CredentialRef> {
+    let secret = serde_json::to_string(credentials)?;
+    if keyring_entry(key).and_then(|e| Ok(e.set_password(&secret)?)).is_ok() {
+        return Ok(CredentialRef { store: CredentialStore::Keyring, key: key.to_string() });
+    }
+
+    let passphrase = passphrase.ok_or_else(|| anyhow!("No keyring available"))?;
+    VaultStore::local()?.store(key, credentials, passphrase)?;
+    Ok(CredentialRef { store: CredentialStore::Vault, key: key.to_string() })
+}
+
+pub fn load(reference: &CredentialRef, passphrase: Option<&str>) -> Result<Credentials> {
+    match reference.store {
+        CredentialStore::Keyring => {
+            let secret = keyring_entry(&reference.key)?.get_password()?;
+            Ok(serde_json::from_str(&secret)?)
+        }
+        CredentialStore::Vault => {
+            let passphrase = passphrase.ok_or_else(|| anyhow!("The vault is locked"))?;
+            VaultStore::local()?.load(&reference.key, passphrase)
+        }
+    }
+}
+
+pub fn forget(reference: &CredentialRef, passphrase: Option<&str>) -> Result<()> {
+    match reference.store {
+        CredentialStore::Keyring => match keyring_entry(&reference.key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        },
+        CredentialStore::Vault => {
+            let passphrase = passphrase.ok_or_else(|| anyhow!("The vault is locked"))?;
+            VaultStore::local()?.forget(&reference.key, passphrase)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthKind {
+    None,
+    Password,
+    Token,
+}
+
+impl AuthKind {
+    pub const ALL: [AuthKind; 3] = [AuthKind::None, AuthKind::Password, AuthKind::Token];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthKind::None => "No auth",
+            AuthKind::Password => "Password",
+            AuthKind::Token => "Token",
+        }
+    }
+}
+
+/// Credentials typed next to the host; the secret is cleared once Connect has used it.
+pub struct LoginForm {
+    pub kind: AuthKind,
+    pub username: String,
+    pub secret: String,
+    pub save: bool,
+    /// Opens the connection read-only
+    pub read_only: bool,
+}
+
+impl Default for LoginForm {
+    fn default() -> Self {
+        Self {
+            kind: AuthKind::None,
+            username: String::new(),
+            secret: String::new(),
+            save: true,
+            read_only: false,
+        }
+    }
+}
+
+impl LoginForm {
+    pub fn credentials(&self) -> Option<Credentials> {
+        match self.kind {
+            AuthKind::None => None,
+            AuthKind::Password => Some(Credentials::Password {
+                username: self.username.trim().to_string(),
+                password: self.secret.clone(),
+            }),
+            AuthKind::Token => Some(Credentials::Token(self.secret.trim().to_string())),
+        }
+    }
+}
+
+/// What the vault passphrase is asked for.
+pub enum VaultRequest {
+    /// The keyring is unavailable, so the connection's credentials go to the vault
+    Save(u64),
+    /// Restored connections whose credentials are in the vault
+    Unlock(Vec<u64>),
+    Forget(u64),
+}
+
+pub struct VaultPrompt {
+    pub request: VaultRequest,
+    pub passphrase: String,
+    pub error: Option<String>,
+}
+
+impl VaultPrompt {
+    pub fn new(request: VaultRequest) -> Self {
+        Self {
+            request,
+            passphrase: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_login_fields(&self, ui: &mut egui::Ui, login: &mut LoginForm) {
+        egui::ComboBox::from_id_salt("auth_kind")
+            .selected_text(login.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in AuthKind::ALL {
+                    ui.selectable_value(&mut login.kind, kind, kind.label());
+                }
+            });
+
+        match login.kind {
+            AuthKind::None => return,
+            AuthKind::Password => {
+                ui.label("User:");
+                ui.add(egui::TextEdit::singleline(&mut login.username).desired_width(90.0));
+                ui.label("Password:");
+            }
+            AuthKind::Token => {
+                ui.label("Token:");
+            }
+        }
+        ui.add(egui::TextEdit::singleline(&mut login.secret).password(true).desired_width(120.0));
+        ui.checkbox(&mut login.save, "Save")
+            .on_hover_text("Keep the credentials in the OS keyring, or in a passphrase-encrypted vault file where there is none");
+    }
+
+    /// Saves the connection's credentials, asking for the vault passphrase if the keyring is unavailable.
+    pub(crate) fn save_credentials(&self, state: &mut AppState, id: u64) {
+        let passphrase = state.vault_passphrase.clone();
+        let Some(connection) = state.connection_by_id(id) else {
+            return;
+        };
+        let Some(credentials) = connection.credentials.clone() else {
+            return;
+        };
+
+        match store(&credential_key(&connection.host, &credentials), &credentials, passphrase.as_deref()) {
+            Ok(reference) => {
+                state.status = format!("Credentials saved in the {}", reference.store.label());
+                if let Some(connection) = state.connection_by_id(id) {
+                    connection.credential = Some(reference);
+                }
+            }
+            Err(_) if passphrase.is_none() => state.vault_prompt = Some(VaultPrompt::new(VaultRequest::Save(id))),
+            Err(e) => state.status = format!("Error saving credentials: {}", e),
+        }
+    }
+
+    pub(crate) fn forget_credentials(&self, state: &mut AppState, id: u64) {
+        let passphrase = state.vault_passphrase.clone();
+        let Some(reference) = state.connection_by_id(id).and_then(|c| c.credential.clone()) else {
+            return;
+        };
+        if reference.store == CredentialStore::Vault && passphrase.is_none() {
+            state.vault_prompt = Some(VaultPrompt::new(VaultRequest::Forget(id)));
+            return;
+        }
+
+        match forget(&reference, passphrase.as_deref()) {
+            Ok(()) => {
+                state.status = format!("Forgot the saved credentials for {}", reference.key);
+                if let Some(connection) = state.connection_by_id(id) {
+                    connection.credential = None;
+                }
+            }
+            Err(e) => state.status = format!("Error: {}", e),
+        }
+    }
+
+    pub(crate) fn show_vault_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(prompt) = &mut state.vault_prompt else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut skip = false;
+        let creating = VaultStore::local().map(|v| !v.exists()).unwrap_or(true);
+
+        egui::Window::new("Credential vault")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match &prompt.request {
+                    VaultRequest::Save(_) => ui.label("No OS keyring is available, so the credentials go to an encrypted vault file."),
+                    VaultRequest::Unlock(ids) => ui.label(format!("{} saved connection(s) keep their credentials in the vault.", ids.len())),
+                    VaultRequest::Forget(_) => ui.label("Unlock the vault to remove the credentials."),
+                };
+                if creating {
+                    ui.weak("Choose a passphrase; it is needed to unlock the vault on later launches.");
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Passphrase:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut prompt.passphrase).password(true));
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        submit = true;
+                    }
+                });
+                if let Some(error) = &prompt.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.horizontal(|ui| {
+                    let label = if creating { "Create vault" } else { "Unlock" };
+                    if ui.add_enabled(!prompt.passphrase.is_empty(), egui::Button::new(label)).clicked() {
+                        submit = true;
+                    }
+                    if let VaultRequest::Unlock(_) = prompt.request {
+                        if ui.button("Connect without credentials").clicked() {
+                            skip = true;
+                        }
+                    }
+                });
+            });
+
+        if !open {
+            state.vault_prompt = None;
+            return;
+        }
+        if skip {
+            let Some(VaultPrompt { request: VaultRequest::Unlock(ids), .. }) = state.vault_prompt.take() else {
+                return;
+            };
+            for id in ids {
+                self.reconnect(ctx, state, id);
+            }
+            return;
+        }
+        if !submit || prompt.passphrase.is_empty() {
+            return;
+        }
+
+        // Check the passphrase before anything is written or connected with it
+        let passphrase = prompt.passphrase.clone();
+        if let Err(e) = VaultStore::local().and_then(|v| v.read(&passphrase)) {
+            prompt.error = Some(e.to_string());
+            return;
+        }
+        state.vault_passphrase = Some(passphrase.clone());
+        let Some(prompt) = state.vault_prompt.take() else {
+            return;
+        };
+
+        match prompt.request {
+            VaultRequest::Save(id) => self.save_credentials(state, id),
+            VaultRequest::Forget(id) => self.forget_credentials(state, id),
+            VaultRequest::Unlock(ids) => {
+                for id in ids {
+                    let reference = state.connection_by_id(id).and_then(|c| c.credential.clone());
+                    match reference.map(|r| load(&r, Some(&passphrase))) {
+                        Some(Ok(credentials)) => {
+                            if let Some(connection) = state.connection_by_id(id) {
+                                connection.credentials = Some(credentials);
+                            }
+                        }
+                        Some(Err(e)) => state.status = format!("Error: {}", e),
+                        None => {}
+                    }
+                    self.reconnect(ctx, state, id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vault {
+        let mut vault = Vault::new();
+        vault.insert(
+            "admin@http://localhost:8086".to_string(),
+            Credentials::Password { username: "admin".to_string(), password: "secret".to_string() },
+        );
+        vault.insert("token@http://localhost:8086".to_string(), Credentials::Token("abc123".to_string()));
+        vault
+    }
+
+    fn temp_vault(name: &str) -> VaultStore {
+        let dir = std::env::temp_dir().join(format!("influxdb-browser-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        VaultStore::new(dir.join(VAULT_FILE))
+    }
+
+    fn tampered(text: &str, edit: impl FnOnce(&mut VaultFile)) -> String {
+        let mut file: VaultFile = serde_json::from_str(text).unwrap();
+        edit(&mut file);
+        serde_json::to_string(&file).unwrap()
+    }
+
+    #[test]
+    fn seal_open_round_trip() {
+        let text = seal_with(&sample(), "pass", &[7; SALT_LEN], &[1; 12]).unwrap();
+        assert!(open(&text, "pass").unwrap() == sample());
+        assert!(open(&seal(&sample(), "pass").unwrap(), "pass").unwrap() == sample());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let text = seal(&sample(), "pass").unwrap();
+        assert!(open(&text, "other").is_err());
+    }
+
+    #[test]
+    fn damaged_vaults_are_rejected() {
+        let text = seal(&sample(), "pass").unwrap();
+
+        let flipped = tampered(&text, |file| {
+            let mut bytes = STANDARD.decode(&file.ciphertext).unwrap();
+            bytes[0] ^= 1;
+            file.ciphertext = STANDARD.encode(bytes);
+        });
+        assert!(open(&flipped, "pass").is_err());
+
+        let short_nonce = tampered(&text, |file| file.nonce = STANDARD.encode([0u8; 8]));
+        assert!(open(&short_nonce, "pass").is_err());
+        assert!(seal_with(&sample(), "pass", &[7; SALT_LEN], &[0; 8]).is_err());
+
+        let future = tampered(&text, |file| file.version = VAULT_VERSION + 1);
+        assert!(open(&future, "pass").is_err());
+
+        assert!(open("not json", "pass").is_err());
+    }
+
+    #[test]
+    fn store_load_forget_in_vault_file() {
+        let vault = temp_vault("store");
+        assert!(!vault.exists());
+        assert!(vault.read("pass").unwrap().is_empty());
+
+        let admin = Credentials::Password { username: "admin".to_string(), password: "secret".to_string() };
+        let token = Credentials::Token("abc123".to_string());
+        vault.store("admin@host", &admin, "pass").unwrap();
+        vault.store("token@host", &token, "pass").unwrap();
+        assert!(vault.exists());
+
+        assert!(vault.load("admin@host", "pass").unwrap() == admin);
+        assert!(vault.load("token@host", "pass").unwrap() == token);
+        assert!(vault.load("admin@host", "wrong").is_err());
+        assert!(vault.load("missing@host", "pass").is_err());
+
+        vault.forget("admin@host", "pass").unwrap();
+        assert!(vault.load("admin@host", "pass").is_err());
+        assert!(vault.load("token@host", "pass").unwrap() == token);
+        // Forgetting what isn't there leaves the vault alone
+        vault.forget("admin@host", "pass").unwrap();
+
+        let _ = std::fs::remove_dir_all(vault.path.parent().unwrap());
+    }
+
+    #[test]
+    fn keys_differ_per_login() {
+        let host = "http://localhost:8086";
+        let alice = Credentials::Password { username: "alice".to_string(), password: "a".to_string() };
+        let bob = Credentials::Password { username: "bob".to_string(), password: "b".to_string() };
+        assert_eq!(credential_key(host, &alice), "alice@http://localhost:8086");
+        assert_ne!(credential_key(host, &alice), credential_key(host, &bob));
+        assert_ne!(
+            credential_key(host, &Credentials::Token("one".to_string())),
+            credential_key(host, &Credentials::Token("two".to_string()))
+        );
+    }
+}
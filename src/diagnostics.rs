@@ -0,0 +1,375 @@
+use eframe::egui;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::chart::{self, Line};
+use crate::influx::{self, InfluxClient, Series};
+use crate::ui::{value_to_string, AppState};
+use crate::InfluxDBApp;
+
+/// Title, `_internal` measurement, selected expression and optional tag to split by.
+const CHARTS: [(&str, &str, &str, Option<&str>); 8] = [
+    ("Points written /s", "write", "non_negative_derivative(max(\"pointReq\"), 1s)", None),
+    ("HTTP requests /s", "httpd", "non_negative_derivative(max(\"req\"), 1s)", None),
+    ("Queries executed /s", "queryExecutor", "non_negative_derivative(max(\"queriesExecuted\"), 1s)", None),
+    ("Active queries", "queryExecutor", "max(\"queriesActive\")", None),
+    ("Heap in use (bytes)", "runtime", "max(\"HeapInUse\")", None),
+    ("GC pause (ns/s)", "runtime", "non_negative_derivative(max(\"PauseTotalNs\"), 1s)", None),
+    ("Series per database", "database", "max(\"numSeries\")", Some("database")),
+    ("Measurements per database", "database", "max(\"numMeasurements\")", Some("database")),
+];
+
+/// `(series name, column)` pairs shown on the overview, with their labels.
+const OVERVIEW: [(&str, &str, &str); 12] = [
+    ("runtime", "HeapAlloc", "Heap allocated"),
+    ("runtime", "HeapInUse", "Heap in use"),
+    ("runtime", "NumGC", "GC runs"),
+    ("runtime", "PauseTotalNs", "GC pause total (ns)"),
+    ("runtime", "NumGoroutine", "Goroutines"),
+    ("httpd", "req", "HTTP requests"),
+    ("httpd", "queryReq", "Query requests"),
+    ("httpd", "writeReq", "Write requests"),
+    ("httpd", "pointsWrittenOK", "Points written"),
+    ("queryExecutor", "queriesActive", "Active queries"),
+    ("queryExecutor", "queriesExecuted", "Queries executed"),
+    ("queryExecutor", "queriesFinished", "Queries finished"),
+];
+
+const SHARD_COLUMNS: [&str; 4] = ["diskBytes", "seriesCreate", "writePointsOk", "writeReq"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsTab {
+    Overview,
+    Charts,
+    Shards,
+    Stats,
+    Diagnostics,
+}
+
+impl DiagnosticsTab {
+    const ALL: [DiagnosticsTab; 5] = [
+        DiagnosticsTab::Overview,
+        DiagnosticsTab::Charts,
+        DiagnosticsTab::Shards,
+        DiagnosticsTab::Stats,
+        DiagnosticsTab::Diagnostics,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            DiagnosticsTab::Overview => "Overview",
+            DiagnosticsTab::Charts => "Charts",
+            DiagnosticsTab::Shards => "Shards",
+            DiagnosticsTab::Stats => "SHOW STATS",
+            DiagnosticsTab::Diagnostics => "SHOW DIAGNOSTICS",
+        }
+    }
+}
+
+pub struct ChartData {
+    pub title: String,
+    pub lines: Vec<Line>,
+    pub error: Option<String>,
+}
+
+pub struct DiagnosticsView {
+    /// Connection the window was opened on
+    pub connection: u64,
+    pub tab: DiagnosticsTab,
+    pub range: String,
+    pub auto_refresh: bool,
+    pub interval_secs: u64,
+    pub last_refresh: Option<Instant>,
+    pub stats: Vec<Series>,
+    pub diagnostics: Vec<Series>,
+    pub charts: Vec<ChartData>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl DiagnosticsView {
+    pub fn new(connection: u64) -> Self {
+        Self {
+            connection,
+            tab: DiagnosticsTab::Overview,
+            range: "1h".to_string(),
+            auto_refresh: true,
+            interval_secs: 10,
+            last_refresh: None,
+            stats: Vec::new(),
+            diagnostics: Vec::new(),
+            charts: Vec::new(),
+            loading: false,
+            error: None,
+        }
+    }
+}
+
+/// The first value of `column` in the first series called `name`.
+fn stat<'a>(series: &'a [Series], name: &str, column: &str) -> Option<&'a Value> {
+    let s = series.iter().find(|s| s.name.as_deref() == Some(name))?;
+    let idx = s.columns.iter().position(|c| c == column)?;
+    s.values.first()?.get(idx)
+}
+
+fn tag<'a>(series: &'a Series, key: &str) -> &'a str {
+    series.tags.get(key).and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+fn format_value(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Number(n)) => n.as_f64().map(chart::format_number).unwrap_or_else(|| n.to_string()),
+        Some(v) => value_to_string(v),
+        None => "-".to_string(),
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_diagnostics_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.diagnostics.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(client) = state.connection_of(id).map(|c| c.client.clone()) else {
+            state.diagnostics = None;
+            return;
+        };
+        let Some(view) = &mut state.diagnostics else {
+            return;
+        };
+
+        let mut open = true;
+        let mut refresh = false;
+
+        egui::Window::new(format!("Server diagnostics - {}", client.host()))
+            .id(egui::Id::new("diagnostics_window"))
+            .open(&mut open)
+            .default_size([800.0, 600.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for tab in DiagnosticsTab::ALL {
+                        ui.selectable_value(&mut view.tab, tab, tab.label());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                    ui.checkbox(&mut view.auto_refresh, "Auto refresh every");
+                    ui.add(egui::DragValue::new(&mut view.interval_secs).range(2..=600).suffix(" s"));
+                    ui.label("Chart range:");
+                    if ui.add(egui::TextEdit::singleline(&mut view.range).desired_width(50.0)).lost_focus() {
+                        refresh = true;
+                    }
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| match view.tab {
+                    DiagnosticsTab::Overview => overview(ui, &view.stats),
+                    DiagnosticsTab::Charts => {
+                        for data in &view.charts {
+                            ui.strong(&data.title);
+                            match &data.error {
+                                Some(err) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, err);
+                                }
+                                None => chart::line_chart(ui, &data.lines, 140.0),
+                            }
+                            ui.add_space(8.0);
+                        }
+                    }
+                    DiagnosticsTab::Shards => shards(ui, &view.stats),
+                    DiagnosticsTab::Stats => series_list(ui, "stats", &view.stats),
+                    DiagnosticsTab::Diagnostics => series_list(ui, "diagnostics", &view.diagnostics),
+                });
+            });
+
+        if !open {
+            state.diagnostics = None;
+            return;
+        }
+
+        if view.auto_refresh && !view.loading {
+            let interval = Duration::from_secs(view.interval_secs);
+            match view.last_refresh {
+                Some(last) if last.elapsed() < interval => ctx.request_repaint_after(interval - last.elapsed()),
+                _ => refresh = true,
+            }
+        }
+
+        if !refresh || view.loading {
+            return;
+        }
+
+        view.loading = true;
+        view.last_refresh = Some(Instant::now());
+        let range = view.range.clone();
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            Self::load_diagnostics(state_clone, ctx_clone, client, range).await;
+        });
+    }
+
+    async fn load_diagnostics(state: Arc<Mutex<AppState>>, ctx: egui::Context, client: InfluxClient, range: String) {
+        let result = async {
+            let stats = client.show_stats().await?;
+            let diagnostics = client.show_diagnostics().await?;
+            anyhow::Ok((stats, diagnostics))
+        }
+        .await;
+
+        // Charts fail one by one when `_internal` is disabled or missing a measurement
+        let mut charts = Vec::new();
+        match influx::parse_duration(&range) {
+            Some(range_ns) => {
+                // About 120 buckets over the range, never below the 10s `_internal` sampling
+                let bucket = (range_ns / 120 / 1_000_000_000).max(10);
+                for (title, measurement, expression, split) in CHARTS {
+                    let group = split.map(|t| format!(", {}", influx::quote_ident(t))).unwrap_or_default();
+                    let query = format!(
+                        "SELECT {} FROM \"_internal\"..{} WHERE time > now() - {} GROUP BY time({}s){} fill(none)",
+                        expression,
+                        influx::quote_ident(measurement),
+                        range.trim(),
+                        bucket,
+                        group
+                    );
+                    let (lines, error) = match client.query_series(&query, Some("_internal")).await {
+                        Ok(series) => (series.iter().map(|s| series_line(s, split)).collect(), None),
+                        Err(e) => (Vec::new(), Some(format!("Error: {}", e))),
+                    };
+                    charts.push(ChartData {
+                        title: title.to_string(),
+                        lines,
+                        error,
+                    });
+                }
+            }
+            None => charts.push(ChartData {
+                title: "Charts".to_string(),
+                lines: Vec::new(),
+                error: Some(format!("Invalid range '{}'", range)),
+            }),
+        }
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.diagnostics {
+                view.loading = false;
+                view.last_refresh = Some(Instant::now());
+                view.charts = charts;
+                match result {
+                    Ok((stats, diagnostics)) => {
+                        view.stats = stats;
+                        view.diagnostics = diagnostics;
+                        view.error = None;
+                    }
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
+
+fn series_line(series: &Series, split: Option<&str>) -> Line {
+    let points = series
+        .values
+        .iter()
+        .filter_map(|row| {
+            let time = chrono::DateTime::parse_from_rfc3339(row.first()?.as_str()?).ok()?;
+            let value = row.get(1)?.as_f64()?;
+            Some([time.timestamp_millis() as f64 / 1000.0, value])
+        })
+        .collect();
+    Line {
+        name: split.map(|t| tag(series, t).to_string()).unwrap_or_default(),
+        points,
+    }
+}
+
+fn overview(ui: &mut egui::Ui, stats: &[Series]) {
+    egui::Grid::new("diagnostics_overview").striped(true).num_columns(2).show(ui, |ui| {
+        for (name, column, label) in OVERVIEW {
+            ui.label(label);
+            ui.label(format_value(stat(stats, name, column)));
+            ui.end_row();
+        }
+    });
+
+    ui.separator();
+    ui.strong("Databases");
+    egui::Grid::new("diagnostics_databases").striped(true).num_columns(3).show(ui, |ui| {
+        ui.strong("Database");
+        ui.strong("Series");
+        ui.strong("Measurements");
+        ui.end_row();
+        for series in stats.iter().filter(|s| s.name.as_deref() == Some("database")) {
+            ui.label(tag(series, "database"));
+            let slice = std::slice::from_ref(series);
+            ui.label(format_value(stat(slice, "database", "numSeries")));
+            ui.label(format_value(stat(slice, "database", "numMeasurements")));
+            ui.end_row();
+        }
+    });
+}
+
+fn shards(ui: &mut egui::Ui, stats: &[Series]) {
+    egui::Grid::new("diagnostics_shards").striped(true).show(ui, |ui| {
+        ui.strong("Shard");
+        ui.strong("Database");
+        ui.strong("RP");
+        for column in SHARD_COLUMNS {
+            ui.strong(column);
+        }
+        ui.end_row();
+
+        for series in stats.iter().filter(|s| s.name.as_deref() == Some("shard")) {
+            ui.label(tag(series, "id"));
+            ui.label(tag(series, "database"));
+            ui.label(tag(series, "retentionPolicy"));
+            let slice = std::slice::from_ref(series);
+            for column in SHARD_COLUMNS {
+                ui.label(format_value(stat(slice, "shard", column)));
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// Every series as a collapsible list of its column values.
+fn series_list(ui: &mut egui::Ui, id: &str, series: &[Series]) {
+    for (i, s) in series.iter().enumerate() {
+        let mut title = s.name.clone().unwrap_or_default();
+        let tags: Vec<String> = s
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, value_to_string(v)))
+            .collect();
+        if !tags.is_empty() {
+            title.push_str(&format!(" ({})", tags.join(", ")));
+        }
+
+        egui::CollapsingHeader::new(title).id_salt((id, i)).show(ui, |ui| {
+            egui::Grid::new((id, i, "grid")).striped(true).num_columns(2).show(ui, |ui| {
+                for row in &s.values {
+                    for (column, value) in s.columns.iter().zip(row) {
+                        ui.label(column);
+                        ui.label(value_to_string(value));
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,452 @@
+use anyhow::{anyhow, Result};
+use eframe::egui::{self, Pos2, Rect, Stroke};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+use crate::chart::{self, format_number};
+use crate::influx::{quote_ident, InfluxClient};
+use crate::stats::percentile;
+use crate::ui::{AppState, DataSource};
+use crate::workspace::WorkspaceTab;
+use crate::InfluxDBApp;
+
+// Outliers drawn in the box plot; more are only counted
+const MAX_OUTLIERS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistributionKind {
+    Histogram,
+    BoxPlot,
+}
+
+/// Five-number summary and equal-width bins between min and max.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    /// At most 1.5 IQR beyond the box; min and max when computed on the server
+    pub low_whisker: f64,
+    pub high_whisker: f64,
+    pub outliers: Vec<f64>,
+    pub bins: Vec<u64>,
+}
+
+impl Distribution {
+    pub fn from_values(mut values: Vec<f64>, bins: usize) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let (min, max) = (values[0], values[values.len() - 1]);
+        let q1 = percentile(&values, 25.0)?;
+        let q3 = percentile(&values, 75.0)?;
+        let iqr = q3 - q1;
+        let (low_fence, high_fence) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+
+        let bins = bins.max(1);
+        let mut counts = vec![0u64; bins];
+        for v in &values {
+            counts[bin_index(*v, min, max, bins)] += 1;
+        }
+        let outliers: Vec<f64> = values
+            .iter()
+            .filter(|v| **v < low_fence || **v > high_fence)
+            .copied()
+            .collect();
+        let step = outliers.len().div_ceil(MAX_OUTLIERS).max(1);
+
+        Some(Self {
+            count: values.len() as u64,
+            min,
+            max,
+            q1,
+            median: percentile(&values, 50.0)?,
+            q3,
+            low_whisker: values.iter().copied().find(|v| *v >= low_fence).unwrap_or(min),
+            high_whisker: values.iter().rev().copied().find(|v| *v <= high_fence).unwrap_or(max),
+            outliers: outliers.into_iter().step_by(step).collect(),
+            bins: counts,
+        })
+    }
+
+    /// Lower and upper edge of bin `i`.
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        bin_range(self.min, self.max, self.bins.len(), i)
+    }
+}
+
+fn bin_range(min: f64, max: f64, bins: usize, i: usize) -> (f64, f64) {
+    let width = (max - min) / bins as f64;
+    (min + width * i as f64, min + width * (i + 1) as f64)
+}
+
+fn bin_index(value: f64, min: f64, max: f64, bins: usize) -> usize {
+    if max <= min {
+        return 0;
+    }
+    (((value - min) / (max - min) * bins as f64) as usize).min(bins - 1)
+}
+
+/// Histogram/box plot panel of one numeric column of a tab's result.
+pub struct DistributionView {
+    pub visible: bool,
+    pub column: Option<String>,
+    pub kind: DistributionKind,
+    pub bins: usize,
+    pub log_scale: bool,
+    /// Computed over the loaded rows, keyed by row count, column and bin count
+    local: Option<(usize, String, usize, Option<Distribution>)>,
+    /// Computed with InfluxQL over the measurement's full time range
+    pub server: Option<(String, Distribution)>,
+    pub use_server: bool,
+    pub server_loading: bool,
+    pub server_error: Option<String>,
+}
+
+impl Default for DistributionView {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            column: None,
+            kind: DistributionKind::Histogram,
+            bins: 30,
+            log_scale: false,
+            local: None,
+            server: None,
+            use_server: false,
+            server_loading: false,
+            server_error: None,
+        }
+    }
+}
+
+impl Clone for DistributionView {
+    // A running server-side computation stays with the original tab
+    fn clone(&self) -> Self {
+        Self {
+            visible: self.visible,
+            column: self.column.clone(),
+            kind: self.kind,
+            bins: self.bins,
+            log_scale: self.log_scale,
+            local: None,
+            server: self.server.clone(),
+            use_server: self.use_server,
+            server_loading: false,
+            server_error: self.server_error.clone(),
+        }
+    }
+}
+
+impl DistributionView {
+    /// Drops the cached local distribution; for when rows were replaced rather than appended.
+    pub fn invalidate(&mut self) {
+        self.local = None;
+    }
+
+    fn local(&mut self, columns: &[String], rows: &[Vec<Value>], column: &str) -> Option<&Distribution> {
+        let stale = match &self.local {
+            Some((n, c, bins, _)) => *n != rows.len() || c != column || *bins != self.bins,
+            None => true,
+        };
+        if stale {
+            let values = columns
+                .iter()
+                .position(|c| c == column)
+                .map(|i| rows.iter().filter_map(|r| r.get(i).and_then(|v| v.as_f64())).collect())
+                .unwrap_or_default();
+            let distribution = Distribution::from_values(values, self.bins);
+            self.local = Some((rows.len(), column.to_string(), self.bins, distribution));
+        }
+        self.local.as_ref().and_then(|(_, _, _, d)| d.as_ref())
+    }
+}
+
+fn row_f64(row: &[Value], i: usize) -> Result<f64> {
+    row.get(i)
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| anyhow!("No numeric values in the field"))
+}
+
+/// Summary and bins over the whole measurement. InfluxQL has no histogram function, so each bin is one `COUNT()`.
+async fn server_distribution(client: &InfluxClient, source: &DataSource, field: &str, bins: usize) -> Result<Distribution> {
+    let f = quote_ident(field);
+    let summary = format!(
+        "COUNT({f}), MIN({f}), PERCENTILE({f}, 25), MEDIAN({f}), PERCENTILE({f}, 75), MAX({f})",
+        f = f
+    );
+    let query = source.select_fields(&summary, None);
+    let (_, rows) = client
+        .query(&query, Some(&source.db))
+        .await?
+        .ok_or_else(|| anyhow!("No data"))?;
+    let row = rows.first().ok_or_else(|| anyhow!("No data"))?;
+    // Column 0 is time
+    let (count, min, q1, median, q3, max) = (
+        row_f64(row, 1)? as u64,
+        row_f64(row, 2)?,
+        row_f64(row, 3)?,
+        row_f64(row, 4)?,
+        row_f64(row, 5)?,
+        row_f64(row, 6)?,
+    );
+
+    let bins = if max > min { bins.max(1) } else { 1 };
+    let mut counts = Vec::with_capacity(bins);
+    for i in 0..bins {
+        let (low, high) = bin_range(min, max, bins, i);
+        // The last bin includes the maximum itself
+        let condition = if i + 1 == bins {
+            format!("{f} >= {} AND {f} <= {}", low, max, f = f)
+        } else {
+            format!("{f} >= {} AND {f} < {}", low, high, f = f)
+        };
+        let query = source.select_fields(&format!("COUNT({})", f), Some(condition));
+        let count = client
+            .query(&query, Some(&source.db))
+            .await?
+            .and_then(|(_, rows)| rows.first().and_then(|r| r.get(1)).and_then(|v| v.as_u64()))
+            .unwrap_or(0);
+        counts.push(count);
+    }
+
+    Ok(Distribution {
+        count,
+        min,
+        max,
+        q1,
+        median,
+        q3,
+        low_whisker: min,
+        high_whisker: max,
+        outliers: Vec::new(),
+        bins: counts,
+    })
+}
+
+fn histogram(ui: &mut egui::Ui, distribution: &Distribution, log_scale: bool, height: f32) {
+    let width = ui.available_width().max(200.0);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    let text_color = visuals.weak_text_color();
+    let font = egui::FontId::proportional(11.0);
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let scale = |count: u64| if log_scale { (count as f64).ln_1p() } else { count as f64 };
+    let top = distribution.bins.iter().copied().map(scale).fold(0.0, f64::max).max(1.0);
+    let plot = Rect::from_min_max(rect.min + egui::vec2(50.0, 8.0), rect.max - egui::vec2(8.0, 18.0));
+    let bar_width = plot.width() / distribution.bins.len() as f32;
+
+    for i in 0..=4 {
+        let y = plot.bottom() - plot.height() * i as f32 / 4.0;
+        let value = top * i as f64 / 4.0;
+        let label = if log_scale { value.exp_m1() } else { value };
+        painter.line_segment([Pos2::new(plot.left(), y), Pos2::new(plot.right(), y)], Stroke::new(1.0, visuals.faint_bg_color));
+        painter.text(Pos2::new(plot.left() - 4.0, y), egui::Align2::RIGHT_CENTER, format_number(label.round()), font.clone(), text_color);
+    }
+    painter.text(plot.left_bottom() + egui::vec2(0.0, 2.0), egui::Align2::LEFT_TOP, format_number(distribution.min), font.clone(), text_color);
+    painter.text(plot.right_bottom() + egui::vec2(0.0, 2.0), egui::Align2::RIGHT_TOP, format_number(distribution.max), font, text_color);
+
+    for (i, count) in distribution.bins.iter().enumerate() {
+        let h = (scale(*count) / top) as f32 * plot.height();
+        let left = plot.left() + bar_width * i as f32;
+        let bar = Rect::from_min_max(Pos2::new(left + 0.5, plot.bottom() - h), Pos2::new(left + bar_width - 0.5, plot.bottom()));
+        painter.rect_filled(bar, 0.0, chart::color(0));
+    }
+
+    if let Some(pointer) = response.hover_pos().filter(|p| plot.contains(*p)) {
+        let i = (((pointer.x - plot.left()) / bar_width) as usize).min(distribution.bins.len() - 1);
+        let (low, high) = distribution.bin_range(i);
+        response.on_hover_ui_at_pointer(|ui| {
+            ui.label(format!("{} .. {}", format_number(low), format_number(high)));
+            ui.label(format!("{} points", distribution.bins[i]));
+        });
+    }
+}
+
+/// Horizontal box plots sharing one value axis, one row per distribution.
+fn box_plots(ui: &mut egui::Ui, rows: &[(&str, &Distribution)], log_scale: bool) {
+    let width = ui.available_width().max(200.0);
+    let row_height = 44.0;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, row_height * rows.len() as f32 + 20.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    let text_color = visuals.weak_text_color();
+    let font = egui::FontId::proportional(11.0);
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    // Log scale only makes sense when every value is positive
+    let log_scale = log_scale && rows.iter().all(|(_, d)| d.min > 0.0);
+    let axis = |v: f64| if log_scale { v.log10() } else { v };
+    let low = rows.iter().map(|(_, d)| axis(d.min)).fold(f64::MAX, f64::min);
+    let mut high = rows.iter().map(|(_, d)| axis(d.max)).fold(f64::MIN, f64::max);
+    if high <= low {
+        high = low + 1.0;
+    }
+    let plot = Rect::from_min_max(rect.min + egui::vec2(90.0, 4.0), rect.max - egui::vec2(12.0, 18.0));
+    let x = |v: f64| plot.left() + ((axis(v) - low) / (high - low)) as f32 * plot.width();
+    let stroke = Stroke::new(1.5, chart::color(0));
+
+    for (i, (label, d)) in rows.iter().enumerate() {
+        let mid = plot.top() + row_height * (i as f32 + 0.5);
+        painter.text(Pos2::new(rect.left() + 6.0, mid), egui::Align2::LEFT_CENTER, *label, font.clone(), visuals.text_color());
+
+        painter.line_segment([Pos2::new(x(d.low_whisker), mid), Pos2::new(x(d.q1), mid)], stroke);
+        painter.line_segment([Pos2::new(x(d.q3), mid), Pos2::new(x(d.high_whisker), mid)], stroke);
+        for whisker in [d.low_whisker, d.high_whisker] {
+            painter.line_segment([Pos2::new(x(whisker), mid - 6.0), Pos2::new(x(whisker), mid + 6.0)], stroke);
+        }
+        let body = Rect::from_min_max(Pos2::new(x(d.q1), mid - 12.0), Pos2::new(x(d.q3).max(x(d.q1) + 1.0), mid + 12.0));
+        painter.rect_stroke(body, 0.0, stroke);
+        painter.line_segment([Pos2::new(x(d.median), mid - 12.0), Pos2::new(x(d.median), mid + 12.0)], Stroke::new(2.5, chart::color(1)));
+        for outlier in &d.outliers {
+            painter.circle_stroke(Pos2::new(x(*outlier), mid), 2.0, Stroke::new(1.0, text_color));
+        }
+    }
+
+    let label = |v: f64| format_number(if log_scale { 10f64.powf(v) } else { v });
+    painter.text(plot.left_bottom() + egui::vec2(0.0, 2.0), egui::Align2::LEFT_TOP, label(low), font.clone(), text_color);
+    painter.text(plot.right_bottom() + egui::vec2(0.0, 2.0), egui::Align2::RIGHT_TOP, label(high), font, text_color);
+}
+
+fn summary(ui: &mut egui::Ui, label: &str, d: &Distribution) {
+    ui.label(format!(
+        "{}: {} points, min {}, q1 {}, median {}, q3 {}, max {}",
+        label,
+        d.count,
+        format_number(d.min),
+        format_number(d.q1),
+        format_number(d.median),
+        format_number(d.q3),
+        format_number(d.max)
+    ));
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_distribution(&self, ctx: &egui::Context, ui: &mut egui::Ui, tab: &mut WorkspaceTab) {
+        let numeric = chart::numeric_columns(&tab.current_columns, &tab.current_data);
+        let view = &mut tab.distribution;
+        if view.column.as_ref().map(|c| !numeric.contains(c)).unwrap_or(true) {
+            view.column = numeric.first().cloned();
+        }
+        let Some(column) = view.column.clone() else {
+            ui.weak("No numeric columns in the result");
+            return;
+        };
+
+        let mut compute = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(("distribution_column", tab.id))
+                .selected_text(&column)
+                .show_ui(ui, |ui| {
+                    for c in &numeric {
+                        ui.selectable_value(&mut view.column, Some(c.clone()), c);
+                    }
+                });
+            ui.selectable_value(&mut view.kind, DistributionKind::Histogram, "Histogram");
+            ui.selectable_value(&mut view.kind, DistributionKind::BoxPlot, "Box plot");
+            if view.kind == DistributionKind::Histogram {
+                ui.add(egui::Slider::new(&mut view.bins, 5..=100).text("bins"));
+            }
+            ui.checkbox(&mut view.log_scale, "Log scale");
+
+            ui.separator();
+            let can_compute = tab.source.is_some() && tab.client.is_some() && !view.server_loading;
+            if ui
+                .add_enabled(can_compute, egui::Button::new("Full range (server)"))
+                .on_hover_text("Compute over the measurement's whole time range with InfluxQL instead of the loaded rows")
+                .on_disabled_hover_text("Needs a measurement opened from the sidebar")
+                .clicked()
+            {
+                compute = true;
+            }
+            if view.server_loading {
+                ui.spinner();
+            }
+            if view.server.as_ref().is_some_and(|(c, _)| *c == column) {
+                ui.checkbox(&mut view.use_server, "Show full range");
+            }
+        });
+        if let Some(error) = &view.server_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let server = view.server.as_ref().filter(|(c, _)| *c == column).map(|(_, d)| d.clone());
+        let (log_scale, kind, use_server) = (view.log_scale, view.kind, view.use_server);
+        let local = view.local(&tab.current_columns, &tab.current_data, &column).cloned();
+
+        match kind {
+            DistributionKind::Histogram => {
+                let shown = match (&server, use_server) {
+                    (Some(server), true) => Some(("Full range", server)),
+                    _ => local.as_ref().map(|d| ("Loaded rows", d)),
+                };
+                if let Some((label, d)) = shown {
+                    histogram(ui, d, log_scale, 180.0);
+                    summary(ui, label, d);
+                }
+            }
+            DistributionKind::BoxPlot => {
+                let rows: Vec<(&str, &Distribution)> = local
+                    .iter()
+                    .map(|d| ("Loaded rows", d))
+                    .chain(server.iter().map(|d| ("Full range", d)))
+                    .collect();
+                if !rows.is_empty() {
+                    box_plots(ui, &rows, log_scale);
+                    for (label, d) in rows {
+                        summary(ui, label, d);
+                    }
+                }
+            }
+        }
+
+        if !compute {
+            return;
+        }
+        let (Some(client), Some(source)) = (tab.client.clone(), tab.source.clone()) else {
+            return;
+        };
+        let view = &mut tab.distribution;
+        view.server_loading = true;
+        view.server_error = None;
+        let bins = view.bins;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+        let tab_id = tab.id;
+
+        self.runtime.spawn(async move {
+            Self::load_server_distribution(state_clone, ctx_clone, tab_id, client, source, column, bins).await;
+        });
+    }
+
+    async fn load_server_distribution(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        tab_id: u64,
+        client: InfluxClient,
+        source: DataSource,
+        column: String,
+        bins: usize,
+    ) {
+        let result = server_distribution(&client, &source, &column, bins).await;
+
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            let view = &mut tab.distribution;
+            view.server_loading = false;
+            match result {
+                Ok(distribution) => {
+                    view.server = Some((column, distribution));
+                    view.use_server = true;
+                }
+                Err(e) => view.server_error = Some(format!("Error: {}", e)),
+            }
+        }
+        ctx.request_repaint();
+    }
+}
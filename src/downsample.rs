@@ -0,0 +1,577 @@
+use anyhow::anyhow;
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::copy::parse_time;
+use crate::influx::{self, InfluxClient};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+pub const FUNCTIONS: [&str; 10] = [
+    "mean", "median", "min", "max", "sum", "count", "first", "last", "spread", "stddev",
+];
+
+// Only these make sense on string and boolean fields
+const ANY_TYPE_FUNCTIONS: [&str; 3] = ["count", "first", "last"];
+
+pub struct FieldAggregates {
+    pub name: String,
+    pub field_type: String,
+    pub functions: [bool; FUNCTIONS.len()],
+}
+
+impl FieldAggregates {
+    fn is_numeric(&self) -> bool {
+        matches!(self.field_type.as_str(), "float" | "integer" | "unsigned")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowStatus {
+    Pending,
+    Running,
+    Done(u64),
+    Failed(String),
+}
+
+pub struct JobWindow {
+    pub start: i64,
+    pub end: i64,
+    pub attempts: u32,
+    pub status: WindowStatus,
+}
+
+pub struct DownsampleView {
+    /// Connection the job reads from and writes to
+    pub connection: u64,
+    pub database: String,
+    pub rp: String,
+    pub measurement: String,
+    pub fields: Vec<FieldAggregates>,
+    pub interval: String,
+    pub start: String,
+    pub end: String,
+    pub intervals_per_window: u32,
+    pub target_rp: String,
+    pub target_measurement: String,
+    pub max_retries: u32,
+    pub windows: Vec<JobWindow>,
+    pub running: bool,
+    pub cancel: bool,
+    pub verification: Option<(u64, u64)>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl DownsampleView {
+    pub fn new(connection: u64, database: String, rp: Option<&str>, measurement: String) -> Self {
+        Self {
+            connection,
+            database,
+            rp: rp.unwrap_or_default().to_string(),
+            target_measurement: format!("{}_1h", measurement),
+            measurement,
+            fields: Vec::new(),
+            interval: "1h".to_string(),
+            start: String::new(),
+            end: String::new(),
+            intervals_per_window: 24,
+            target_rp: String::new(),
+            max_retries: 3,
+            windows: Vec::new(),
+            running: false,
+            cancel: false,
+            verification: None,
+            loading: true,
+            error: None,
+        }
+    }
+
+    fn select_list(&self) -> String {
+        self.fields
+            .iter()
+            .flat_map(|f| {
+                FUNCTIONS
+                    .iter()
+                    .zip(f.functions)
+                    .filter(|(_, on)| *on)
+                    .map(move |(func, _)| {
+                        format!(
+                            "{}({}) AS {}",
+                            func,
+                            influx::quote_ident(&f.name),
+                            influx::quote_ident(&format!("{}_{}", func, f.name))
+                        )
+                    })
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn rp(&self) -> Option<&str> {
+        Some(self.rp.trim()).filter(|rp| !rp.is_empty())
+    }
+
+    fn target(&self) -> String {
+        // INTO needs the database spelled out; `"db".."m"` writes to the default RP
+        let rp = match self.target_rp.trim() {
+            "" => String::new(),
+            rp => influx::quote_ident(rp),
+        };
+        format!(
+            "{}.{}.{}",
+            influx::quote_ident(&self.database),
+            rp,
+            influx::quote_ident(self.target_measurement.trim())
+        )
+    }
+
+    /// The statement run for one window; `GROUP BY *` keeps the source tags as tags.
+    fn statement(&self, start: i64, end: i64) -> String {
+        format!(
+            "SELECT {} INTO {} FROM {} WHERE time >= {} AND time < {} GROUP BY time({}), *",
+            self.select_list(),
+            self.target(),
+            influx::qualified_measurement(&self.database, self.rp(), &self.measurement),
+            start,
+            end,
+            self.interval.trim()
+        )
+    }
+
+    fn validate(&self) -> Result<i64, String> {
+        if self.select_list().is_empty() {
+            return Err("Pick at least one aggregate".to_string());
+        }
+        if self.target_measurement.trim().is_empty() {
+            return Err("Target measurement is required".to_string());
+        }
+        let interval = influx::parse_duration(&self.interval).filter(|d| *d > 0);
+        interval.ok_or_else(|| format!("Invalid interval '{}'", self.interval))
+    }
+
+    fn is_active(&self) -> bool {
+        self.running || self.loading
+    }
+}
+
+enum Action {
+    Run,
+    RetryFailed,
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_downsample_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.downsample.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(connection) = state.connection_of(id) else {
+            state.downsample = None;
+            return;
+        };
+        let client = connection.client.clone();
+        let read_only = connection.read_only;
+        let Some(view) = &mut state.downsample else {
+            return;
+        };
+
+        let mut open = true;
+        let mut action = None;
+
+        egui::Window::new(format!("Downsample: {} - {}", view.measurement, client.host()))
+            .id(egui::Id::new("downsample_window"))
+            .open(&mut open)
+            .default_size([620.0, 560.0])
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!view.running, |ui| {
+                    egui::Grid::new("downsample_form").num_columns(2).show(ui, |ui| {
+                        ui.label("Source:");
+                        ui.label(influx::qualified_measurement(&view.database, view.rp(), &view.measurement));
+                        ui.end_row();
+
+                        ui.label("Interval:");
+                        ui.add(egui::TextEdit::singleline(&mut view.interval).desired_width(80.0));
+                        ui.end_row();
+
+                        ui.label("From:");
+                        ui.add(egui::TextEdit::singleline(&mut view.start).hint_text("first point (RFC3339)"));
+                        ui.end_row();
+
+                        ui.label("To:");
+                        ui.add(egui::TextEdit::singleline(&mut view.end).hint_text("now (RFC3339)"));
+                        ui.end_row();
+
+                        ui.label("Target RP:");
+                        ui.add(egui::TextEdit::singleline(&mut view.target_rp).hint_text("default"));
+                        ui.end_row();
+
+                        ui.label("Target measurement:");
+                        ui.text_edit_singleline(&mut view.target_measurement);
+                        ui.end_row();
+
+                        ui.label("Intervals per window:");
+                        ui.add(egui::DragValue::new(&mut view.intervals_per_window).range(1..=100_000));
+                        ui.end_row();
+
+                        ui.label("Retries per window:");
+                        ui.add(egui::DragValue::new(&mut view.max_retries).range(0..=10));
+                        ui.end_row();
+                    });
+
+                    ui.separator();
+                    ui.label("Aggregates");
+
+                    egui::ScrollArea::vertical()
+                        .id_salt("downsample_fields")
+                        .max_height(180.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("downsample_fields_grid").striped(true).show(ui, |ui| {
+                                ui.label("");
+                                for func in FUNCTIONS {
+                                    ui.strong(func);
+                                }
+                                ui.end_row();
+
+                                for field in &mut view.fields {
+                                    let numeric = field.is_numeric();
+                                    ui.label(&field.name).on_hover_text(&field.field_type);
+                                    for (func, on) in FUNCTIONS.iter().zip(field.functions.iter_mut()) {
+                                        let allowed = numeric || ANY_TYPE_FUNCTIONS.contains(func);
+                                        ui.add_enabled(allowed, egui::Checkbox::without_text(on));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                });
+
+                if view.loading {
+                    ui.spinner();
+                }
+
+                ui.separator();
+
+                match view.validate() {
+                    Ok(_) => {
+                        let preview = view.statement(0, 0);
+                        ui.add(egui::Label::new(egui::RichText::new(preview).monospace().weak()).wrap());
+                    }
+                    Err(e) => {
+                        ui.weak(e);
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if view.running {
+                        if ui.add_enabled(!view.cancel, egui::Button::new("Cancel")).clicked() {
+                            view.cancel = true;
+                        }
+                        ui.spinner();
+                    } else {
+                        let ready = !view.loading && view.validate().is_ok();
+                        if ui.add_enabled(ready, egui::Button::new("Run")).clicked() {
+                            action = Some(Action::Run);
+                        }
+                        let failed = view.windows.iter().any(|w| matches!(w.status, WindowStatus::Failed(_)));
+                        if failed && ui.button("Retry failed windows").clicked() {
+                            action = Some(Action::RetryFailed);
+                        }
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                if !view.windows.is_empty() {
+                    let finished = view
+                        .windows
+                        .iter()
+                        .filter(|w| matches!(w.status, WindowStatus::Done(_)))
+                        .count();
+                    let written: u64 = view
+                        .windows
+                        .iter()
+                        .filter_map(|w| match w.status {
+                            WindowStatus::Done(n) => Some(n),
+                            _ => None,
+                        })
+                        .sum();
+                    ui.add(
+                        egui::ProgressBar::new(finished as f32 / view.windows.len() as f32)
+                            .text(format!("{}/{} windows, {} points written", finished, view.windows.len(), written)),
+                    );
+
+                    if let Some((written, counted)) = view.verification {
+                        let text = format!("Verification: {} points in target, {} written", counted, written);
+                        if counted == written {
+                            ui.label(text);
+                        } else {
+                            ui.colored_label(egui::Color32::from_rgb(220, 170, 60), text);
+                        }
+                    }
+
+                    egui::ScrollArea::vertical().id_salt("downsample_windows").show(ui, |ui| {
+                        egui::Grid::new("downsample_windows_grid").striped(true).num_columns(3).show(ui, |ui| {
+                            for window in &view.windows {
+                                ui.monospace(format_ns(window.start));
+                                ui.monospace(format_ns(window.end));
+                                match &window.status {
+                                    WindowStatus::Pending => ui.weak("pending"),
+                                    WindowStatus::Running => ui.label(format!("running (attempt {})", window.attempts)),
+                                    WindowStatus::Done(n) => ui.label(format!("{} points", n)),
+                                    WindowStatus::Failed(e) => ui
+                                        .colored_label(ui.visuals().error_fg_color, format!("failed after {} attempts", window.attempts))
+                                        .on_hover_text(e),
+                                };
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+            });
+
+        if !open && !view.is_active() {
+            state.downsample = None;
+            return;
+        }
+
+        let Some(action) = action else {
+            return;
+        };
+        // The connection may have been made read-only since the window was opened
+        if read_only {
+            view.error = Some(format!("Error: {} is read-only", client.host()));
+            return;
+        }
+
+        view.error = None;
+        view.cancel = false;
+        view.verification = None;
+
+        if let Action::RetryFailed = action {
+            for window in &mut view.windows {
+                if matches!(window.status, WindowStatus::Failed(_)) {
+                    window.status = WindowStatus::Pending;
+                    window.attempts = 0;
+                }
+            }
+        }
+        let fresh = matches!(action, Action::Run);
+        view.running = true;
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+        self.runtime.spawn(async move {
+            Self::run_downsample(state_clone, ctx_clone, client, fresh).await;
+        });
+    }
+
+    pub(crate) fn open_downsample(&self, ctx: &egui::Context, state: &mut AppState, db: String, measurement: String) {
+        let (Some(id), Some(client)) = (state.active_connection, state.client()) else {
+            return;
+        };
+        let rp = state.selected_rp.clone();
+        state.downsample = Some(DownsampleView::new(id, db.clone(), rp.as_deref(), measurement.clone()));
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+        self.runtime.spawn(async move {
+            Self::load_downsample_fields(state_clone, ctx_clone, client, db, rp, measurement).await;
+        });
+    }
+
+    async fn load_downsample_fields(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        db: String,
+        rp: Option<String>,
+        measurement: String,
+    ) {
+        let result = client.show_field_keys(&db, rp.as_deref(), &measurement).await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.downsample {
+                view.loading = false;
+                match result {
+                    Ok(fields) => {
+                        let mut seen = Vec::new();
+                        view.fields = fields
+                            .into_iter()
+                            .filter(|(name, _)| {
+                                // Conflicting types show up once per type; keep the first
+                                let new = !seen.contains(name);
+                                seen.push(name.clone());
+                                new
+                            })
+                            .map(|(name, field_type)| {
+                                let mut functions = [false; FUNCTIONS.len()];
+                                let default = if field_type == "string" || field_type == "boolean" { "last" } else { "mean" };
+                                functions[FUNCTIONS.iter().position(|f| *f == default).unwrap_or(0)] = true;
+                                FieldAggregates { name, field_type, functions }
+                            })
+                            .collect();
+                    }
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    async fn run_downsample(state: Arc<Mutex<AppState>>, ctx: egui::Context, client: InfluxClient, fresh: bool) {
+        let result = async {
+            if fresh {
+                let (db, rp, measurement, start, end, interval, per_window) = {
+                    let state = state.lock().unwrap();
+                    let view = state.downsample.as_ref().ok_or_else(|| anyhow!("Job closed"))?;
+                    (
+                        view.database.clone(),
+                        view.rp().map(String::from),
+                        view.measurement.clone(),
+                        view.start.clone(),
+                        view.end.clone(),
+                        view.validate().map_err(|e| anyhow!(e))?,
+                        view.intervals_per_window as i64,
+                    )
+                };
+
+                let start = match parse_time(&start)? {
+                    Some(t) => t,
+                    None => client
+                        .first_timestamp(&db, rp.as_deref(), &measurement)
+                        .await?
+                        .ok_or_else(|| anyhow!("Source measurement has no data"))?,
+                };
+                let end = match parse_time(&end)? {
+                    Some(t) => t,
+                    None => chrono::Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX),
+                };
+
+                // Windows follow the GROUP BY buckets so no bucket is split across two statements
+                let window = interval
+                    .checked_mul(per_window)
+                    .ok_or_else(|| anyhow!("Window of {} intervals is too long, use fewer intervals per window", per_window))?;
+                let mut windows = Vec::new();
+                let mut window_start = start - start.rem_euclid(interval);
+                while window_start < end {
+                    let window_end = window_start.saturating_add(window);
+                    windows.push(JobWindow {
+                        start: window_start,
+                        end: window_end,
+                        attempts: 0,
+                        status: WindowStatus::Pending,
+                    });
+                    window_start = window_end;
+                }
+
+                let mut state = state.lock().unwrap();
+                if let Some(view) = &mut state.downsample {
+                    view.windows = windows;
+                }
+            }
+            ctx.request_repaint();
+
+            loop {
+                let next = {
+                    let mut state = state.lock().unwrap();
+                    let Some(view) = &mut state.downsample else {
+                        return Ok(());
+                    };
+                    if view.cancel {
+                        return Err(anyhow!("Cancelled"));
+                    }
+                    let Some(index) = view.windows.iter().position(|w| w.status == WindowStatus::Pending) else {
+                        break;
+                    };
+                    let window = &view.windows[index];
+                    (index, view.statement(window.start, window.end), view.max_retries)
+                };
+                let (index, statement, max_retries) = next;
+
+                let mut attempt = 0;
+                let status = loop {
+                    attempt += 1;
+                    Self::with_downsample_window(&state, index, |w| {
+                        w.attempts = attempt;
+                        w.status = WindowStatus::Running;
+                    });
+                    ctx.request_repaint();
+
+                    match client.select_into(&statement, None).await {
+                        Ok(written) => break WindowStatus::Done(written),
+                        Err(e) if attempt > max_retries => break WindowStatus::Failed(e.to_string()),
+                        Err(_) => tokio::time::sleep(Duration::from_secs(1 << (attempt - 1).min(5))).await,
+                    }
+                };
+                Self::with_downsample_window(&state, index, |w| w.status = status);
+                ctx.request_repaint();
+            }
+
+            // Compare what the server reported as written with what is now in the target
+            let (query, db, written, range) = {
+                let state = state.lock().unwrap();
+                let Some(view) = &state.downsample else {
+                    return Ok(());
+                };
+                let written: u64 = view
+                    .windows
+                    .iter()
+                    .filter_map(|w| match w.status {
+                        WindowStatus::Done(n) => Some(n),
+                        _ => None,
+                    })
+                    .sum();
+                let range = (
+                    view.windows.first().map(|w| w.start).unwrap_or(0),
+                    view.windows.last().map(|w| w.end).unwrap_or(0),
+                );
+                (view.target(), view.database.clone(), written, range)
+            };
+            let count_query = format!(
+                "SELECT count(*) FROM {} WHERE time >= {} AND time < {} GROUP BY *",
+                query, range.0, range.1
+            );
+            let counted: u64 = client
+                .query_series(&count_query, Some(&db))
+                .await?
+                .iter()
+                .flat_map(|s| s.values.iter())
+                // A point counts once even if several of its fields are set
+                .map(|row| row.iter().skip(1).filter_map(|v| v.as_u64()).max().unwrap_or(0))
+                .sum();
+
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.downsample {
+                view.verification = Some((written, counted));
+            }
+            anyhow::Ok(())
+        }
+        .await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.downsample {
+                view.running = false;
+                if let Err(e) = result {
+                    view.error = Some(format!("Error: {}", e));
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    fn with_downsample_window(state: &Arc<Mutex<AppState>>, index: usize, f: impl FnOnce(&mut JobWindow)) {
+        let mut state = state.lock().unwrap();
+        if let Some(window) = state.downsample.as_mut().and_then(|v| v.windows.get_mut(index)) {
+            f(window);
+        }
+    }
+}
+
+fn format_ns(ns: i64) -> String {
+    chrono::DateTime::from_timestamp_nanos(ns).format("%Y-%m-%d %H:%M:%S").to_string()
+}
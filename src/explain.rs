@@ -0,0 +1,535 @@
+use anyhow::Result;
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32};
+use std::sync::Arc;
+
+use crate::chart::format_number;
+use crate::influx::{self, parse_go_duration, InfluxClient};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+// Leaf iterators highlighted as the slowest
+const HOT_NODES: usize = 3;
+const HOT: Color32 = Color32::from_rgb(220, 80, 80);
+
+/// A node of the `EXPLAIN ANALYZE` tree; `key: value` lines become attributes, `labels` children become labels.
+#[derive(Debug, Clone, Default)]
+pub struct PlanNode {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Nanoseconds spent executing, from `execution_time` or `total_time`.
+    pub fn execution_ns(&self) -> Option<i64> {
+        self.attribute("execution_time")
+            .or_else(|| self.attribute("total_time"))
+            .and_then(parse_go_duration)
+    }
+
+    /// Sum of the integer attributes matching `filter`, over this node and everything below it.
+    pub fn total(&self, filter: &dyn Fn(&str) -> bool) -> u64 {
+        let own: u64 = self
+            .attributes
+            .iter()
+            .filter(|(k, _)| filter(k))
+            .filter_map(|(_, v)| v.parse::<u64>().ok())
+            .sum();
+        own + self.children.iter().map(|c| c.total(filter)).sum::<u64>()
+    }
+
+    fn has_timed_children(&self) -> bool {
+        self.children.iter().any(|c| c.execution_ns().is_some() || c.has_timed_children())
+    }
+
+    /// Timings of the innermost timed nodes, where the work actually happens.
+    fn leaf_timings(&self, out: &mut Vec<i64>) {
+        match self.execution_ns() {
+            Some(ns) if !self.has_timed_children() => out.push(ns),
+            _ => {}
+        }
+        for child in &self.children {
+            child.leaf_timings(out);
+        }
+    }
+
+    fn shard_ids(&self, out: &mut Vec<String>) {
+        for (k, v) in &self.labels {
+            if k == "shard_id" && !out.contains(v) {
+                out.push(v.clone());
+            }
+        }
+        for child in &self.children {
+            child.shard_ids(out);
+        }
+    }
+}
+
+fn attach(stack: &mut [(usize, PlanNode)], roots: &mut Vec<PlanNode>, node: PlanNode) {
+    match stack.last_mut() {
+        Some((_, parent)) if node.name == "labels" => parent.labels.extend(node.attributes),
+        Some((_, parent)) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// Parses the box-drawing tree printed by `EXPLAIN ANALYZE`, one line per row.
+pub fn parse_analyze(lines: &[String]) -> Option<PlanNode> {
+    let mut stack: Vec<(usize, PlanNode)> = Vec::new();
+    let mut roots = Vec::new();
+
+    for line in lines {
+        let chars: Vec<char> = line.chars().collect();
+        // Each level is indented by four characters and ends in "├── " or "└── "
+        let Some(connector) = chars.iter().position(|c| *c == '├' || *c == '└') else {
+            continue;
+        };
+        let depth = connector / 4;
+        let content: String = chars.iter().skip(connector + 4).collect();
+        let content = content.trim();
+
+        if let Some((key, value)) = content.split_once(": ") {
+            while stack.last().is_some_and(|(d, _)| *d >= depth) {
+                let (_, node) = stack.pop().unwrap();
+                attach(&mut stack, &mut roots, node);
+            }
+            if let Some((_, node)) = stack.last_mut() {
+                node.attributes.push((key.to_string(), value.to_string()));
+            }
+            continue;
+        }
+
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            let (_, node) = stack.pop().unwrap();
+            attach(&mut stack, &mut roots, node);
+        }
+        stack.push((depth, PlanNode::new(content)));
+    }
+    while let Some((_, node)) = stack.pop() {
+        attach(&mut stack, &mut roots, node);
+    }
+
+    match roots.len() {
+        0 => None,
+        1 => roots.pop(),
+        _ => Some(PlanNode {
+            name: "plan".to_string(),
+            children: roots,
+            ..Default::default()
+        }),
+    }
+}
+
+/// One iterator of the `EXPLAIN` output, as its `KEY: value` lines.
+#[derive(Debug, Clone, Default)]
+pub struct PlanSection {
+    pub fields: Vec<(String, String)>,
+}
+
+impl PlanSection {
+    pub fn get(&self, key: &str) -> &str {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()).unwrap_or("")
+    }
+}
+
+const SECTION_COLUMNS: [(&str, &str); 7] = [
+    ("EXPRESSION", "Expression"),
+    ("NUMBER OF SHARDS", "Shards"),
+    ("NUMBER OF SERIES", "Series"),
+    ("CACHED VALUES", "Cached values"),
+    ("NUMBER OF FILES", "Files"),
+    ("NUMBER OF BLOCKS", "Blocks"),
+    ("SIZE OF BLOCKS", "Block bytes"),
+];
+
+/// Splits `EXPLAIN` output into iterators; each starts with its `EXPRESSION` line.
+pub fn parse_explain(lines: &[String]) -> Vec<PlanSection> {
+    let mut sections: Vec<PlanSection> = Vec::new();
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key == "EXPRESSION" || sections.is_empty() {
+            sections.push(PlanSection::default());
+        }
+        if let Some(section) = sections.last_mut() {
+            section.fields.push((key.to_string(), value.to_string()));
+        }
+    }
+    sections
+}
+
+pub struct ExplainView {
+    pub query: String,
+    pub database: Option<String>,
+    pub client: InfluxClient,
+    pub plan: Vec<PlanSection>,
+    pub analyze: Option<PlanNode>,
+    pub raw_plan: Vec<String>,
+    pub raw_analyze: Vec<String>,
+    pub show_raw: bool,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+fn format_ns(ns: i64) -> String {
+    match ns {
+        ns if ns >= 1_000_000_000 => format!("{:.2}s", ns as f64 / 1e9),
+        ns if ns >= 1_000_000 => format!("{:.2}ms", ns as f64 / 1e6),
+        ns if ns >= 1_000 => format!("{:.1}µs", ns as f64 / 1e3),
+        ns => format!("{}ns", ns),
+    }
+}
+
+fn cursors(key: &str) -> bool {
+    key.starts_with("cursors_")
+}
+
+fn blocks_decoded(key: &str) -> bool {
+    key.ends_with("_blocks_decoded")
+}
+
+fn block_bytes(key: &str) -> bool {
+    key.ends_with("_blocks_size_bytes")
+}
+
+fn show_node(ui: &mut egui::Ui, node: &PlanNode, path: &str, total_ns: i64, hot_threshold: i64) {
+    let ns = node.execution_ns();
+    let hot = ns.is_some_and(|ns| ns > 0 && ns >= hot_threshold) && !node.has_timed_children();
+    let id = ui.make_persistent_id(("explain_node", path));
+
+    CollapsingState::load_with_default_open(ui.ctx(), id, true)
+        .show_header(ui, |ui| {
+            let mut name = egui::RichText::new(&node.name).strong();
+            if hot {
+                name = name.color(HOT);
+            }
+            ui.label(name);
+            if !node.labels.is_empty() {
+                let labels: Vec<String> = node.labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                ui.weak(labels.join(" "));
+            }
+            if let Some(ns) = ns {
+                let share = if total_ns > 0 { ns as f32 / total_ns as f32 } else { 0.0 };
+                ui.add(
+                    egui::ProgressBar::new(share.clamp(0.0, 1.0))
+                        .desired_width(80.0)
+                        .fill(if hot { HOT } else { ui.visuals().selection.bg_fill }),
+                );
+                ui.label(format_ns(ns));
+            }
+            if node.name == "create_iterator" {
+                ui.weak(format!(
+                    "cursors {}, blocks {}, {} bytes",
+                    node.total(&cursors),
+                    node.total(&blocks_decoded),
+                    format_number(node.total(&block_bytes) as f64)
+                ));
+            }
+        })
+        .body(|ui| {
+            if !node.attributes.is_empty() {
+                egui::Grid::new(("explain_attributes", path)).striped(true).show(ui, |ui| {
+                    for (key, value) in &node.attributes {
+                        ui.weak(key);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+            }
+            for (i, child) in node.children.iter().enumerate() {
+                show_node(ui, child, &format!("{}/{}", path, i), total_ns, hot_threshold);
+            }
+        });
+}
+
+/// The plan comes back as one text column, a row per line.
+async fn plan_lines(client: &InfluxClient, statement: &str, database: Option<&str>) -> Result<Vec<String>> {
+    Ok(client
+        .query(statement, database)
+        .await?
+        .map(|(_, rows)| {
+            rows.iter()
+                .filter_map(|row| row.first().and_then(|v| v.as_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+impl InfluxDBApp {
+    pub(crate) fn open_explain(&self, ctx: &egui::Context, state: &mut AppState, client: InfluxClient, database: Option<String>, query: String) {
+        state.explain = Some(ExplainView {
+            query,
+            database,
+            client,
+            plan: Vec::new(),
+            analyze: None,
+            raw_plan: Vec::new(),
+            raw_analyze: Vec::new(),
+            show_raw: false,
+            loading: false,
+            error: None,
+        });
+        self.run_explain(ctx, state);
+    }
+
+    fn run_explain(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(view) = &mut state.explain else {
+            return;
+        };
+        view.loading = true;
+        view.error = None;
+        let (client, database, query) = (view.client.clone(), view.database.clone(), view.query.trim().to_string());
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            let plan = plan_lines(&client, &format!("EXPLAIN {}", query), database.as_deref()).await;
+            // ANALYZE actually runs the query
+            let analyze_query = format!("EXPLAIN ANALYZE {}", query);
+            influx::record_issued(&analyze_query);
+            let analyze = plan_lines(&client, &analyze_query, database.as_deref()).await;
+
+            {
+                let mut state = state_clone.lock().unwrap();
+                if let Some(view) = &mut state.explain {
+                    view.loading = false;
+                    match (plan, analyze) {
+                        (Ok(plan), Ok(analyze)) => {
+                            view.plan = parse_explain(&plan);
+                            view.analyze = parse_analyze(&analyze);
+                            view.raw_plan = plan;
+                            view.raw_analyze = analyze;
+                        }
+                        (Err(e), _) | (_, Err(e)) => view.error = Some(format!("Error: {}", e)),
+                    }
+                }
+            }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    pub(crate) fn show_explain_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(view) = &mut state.explain else {
+            return;
+        };
+
+        let mut open = true;
+        let mut rerun = false;
+
+        egui::Window::new("Explain")
+            .open(&mut open)
+            .default_size([760.0, 600.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.monospace(view.query.lines().next().unwrap_or_default());
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!view.loading, egui::Button::new("Run again"))
+                        .on_hover_text("EXPLAIN ANALYZE executes the query")
+                        .clicked()
+                    {
+                        rerun = true;
+                    }
+                    ui.checkbox(&mut view.show_raw, "Raw output");
+                });
+                if let Some(error) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().id_salt("explain_scroll").show(ui, |ui| {
+                    if view.show_raw {
+                        for line in view.raw_plan.iter().chain([String::new()].iter()).chain(view.raw_analyze.iter()) {
+                            ui.monospace(line);
+                        }
+                        return;
+                    }
+
+                    egui::CollapsingHeader::new("Plan (EXPLAIN)").default_open(true).show(ui, |ui| {
+                        egui::Grid::new("explain_sections").striped(true).show(ui, |ui| {
+                            for (_, header) in SECTION_COLUMNS {
+                                ui.strong(header);
+                            }
+                            ui.end_row();
+                            for section in &view.plan {
+                                for (key, _) in SECTION_COLUMNS {
+                                    ui.label(section.get(key));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    let Some(root) = &view.analyze else {
+                        return;
+                    };
+                    egui::CollapsingHeader::new("Execution (EXPLAIN ANALYZE)").default_open(true).show(ui, |ui| {
+                        let total_ns = root
+                            .execution_ns()
+                            .or_else(|| root.children.iter().filter_map(|c| c.execution_ns()).max())
+                            .unwrap_or(0);
+                        let mut shards = Vec::new();
+                        root.shard_ids(&mut shards);
+                        ui.label(format!(
+                            "Total {}, {} shard(s), {} cursor(s), {} block(s) decoded, {} bytes",
+                            format_ns(total_ns),
+                            shards.len(),
+                            root.total(&cursors),
+                            root.total(&blocks_decoded),
+                            format_number(root.total(&block_bytes) as f64)
+                        ));
+
+                        let mut timings = Vec::new();
+                        root.leaf_timings(&mut timings);
+                        timings.sort_unstable_by(|a, b| b.cmp(a));
+                        let hot_threshold = timings.get(HOT_NODES.min(timings.len()).saturating_sub(1)).copied().unwrap_or(i64::MAX);
+
+                        show_node(ui, root, "root", total_ns, hot_threshold);
+                    });
+                });
+            });
+
+        if !open {
+            state.explain = None;
+            return;
+        }
+        if rerun {
+            self.run_explain(ctx, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // As printed by InfluxDB 1.8 for `EXPLAIN ANALYZE SELECT max(value) FROM cpu WHERE time > now() - 1h`
+    const ANALYZE: &str = "EXPLAIN ANALYZE
+---------------
+.
+└── select
+    ├── execution_time: 2.25823ms
+    ├── planning_time: 18.381616ms
+    ├── total_time: 20.639846ms
+    └── field_iterators
+        ├── labels
+        │   └── statement: SELECT max(value) FROM \"telegraf\".\"autogen\".cpu WHERE time > now() - 1h
+        └── expression
+            ├── labels
+            │   └── expr: max(value::float)
+            └── create_iterator
+                ├── labels
+                │   ├── measurement: cpu
+                │   └── shard_id: 608
+                ├── cursors_ref: 779
+                ├── cursors_aux: 0
+                ├── cursors_cond: 0
+                ├── float_blocks_decoded: 431
+                ├── float_blocks_size_bytes: 1003552
+                ├── integer_blocks_decoded: 0
+                ├── integer_blocks_size_bytes: 0
+                ├── unsigned_blocks_decoded: 0
+                ├── unsigned_blocks_size_bytes: 0
+                ├── string_blocks_decoded: 0
+                ├── string_blocks_size_bytes: 0
+                ├── boolean_blocks_decoded: 0
+                ├── boolean_blocks_size_bytes: 0
+                └── planning_time: 14.805277ms";
+
+    const EXPLAIN: &str = "QUERY PLAN
+----------
+EXPRESSION: <nil>
+AUXILIARY FIELDS: \"water_level\"::float
+NUMBER OF SHARDS: 1
+NUMBER OF SERIES: 1
+CACHED VALUES: 0
+NUMBER OF FILES: 1
+NUMBER OF BLOCKS: 1
+SIZE OF BLOCKS: 4045";
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    fn names(node: &PlanNode) -> Vec<&str> {
+        node.children.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn analyze_tree_nests_by_indentation() {
+        let root = parse_analyze(&lines(ANALYZE)).unwrap();
+        assert_eq!(root.name, "select");
+        assert_eq!(names(&root), vec!["field_iterators"]);
+        assert_eq!(root.attribute("planning_time"), Some("18.381616ms"));
+
+        let fields = &root.children[0];
+        assert_eq!(names(fields), vec!["expression"]);
+        let expression = &fields.children[0];
+        assert_eq!(names(expression), vec!["create_iterator"]);
+        let iterator = &expression.children[0];
+        assert!(iterator.children.is_empty());
+        assert_eq!(iterator.attributes.len(), 14);
+        assert_eq!(iterator.attribute("planning_time"), Some("14.805277ms"));
+    }
+
+    #[test]
+    fn labels_attach_to_their_parent() {
+        let root = parse_analyze(&lines(ANALYZE)).unwrap();
+        let fields = &root.children[0];
+        assert_eq!(fields.labels[0].0, "statement");
+        let expression = &fields.children[0];
+        assert_eq!(expression.labels, vec![("expr".to_string(), "max(value::float)".to_string())]);
+        let iterator = &expression.children[0];
+        assert_eq!(
+            iterator.labels,
+            vec![("measurement".to_string(), "cpu".to_string()), ("shard_id".to_string(), "608".to_string())]
+        );
+
+        let mut shards = Vec::new();
+        root.shard_ids(&mut shards);
+        assert_eq!(shards, vec!["608".to_string()]);
+    }
+
+    #[test]
+    fn analyze_totals() {
+        let root = parse_analyze(&lines(ANALYZE)).unwrap();
+        assert_eq!(root.execution_ns(), Some(2_258_230));
+        assert_eq!(root.children[0].execution_ns(), None);
+        assert_eq!(root.total(&cursors), 779);
+        assert_eq!(root.total(&blocks_decoded), 431);
+        assert_eq!(root.total(&block_bytes), 1_003_552);
+    }
+
+    #[test]
+    fn analyze_without_a_tree() {
+        assert!(parse_analyze(&lines("EXPLAIN ANALYZE\n---------------\n.")).is_none());
+    }
+
+    #[test]
+    fn explain_sections() {
+        let sections = parse_explain(&lines(EXPLAIN));
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].get("EXPRESSION"), "<nil>");
+        assert_eq!(sections[0].get("NUMBER OF SHARDS"), "1");
+        assert_eq!(sections[0].get("SIZE OF BLOCKS"), "4045");
+        assert_eq!(sections[0].get("MISSING"), "");
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// One table from a Flux result stream, identified by its group key.
+#[derive(Debug, Clone)]
+pub struct FluxTable {
+    pub result: String,
+    pub table: String,
+    pub group_key: Vec<(String, String)>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl FluxTable {
+    pub fn group_key_label(&self) -> String {
+        if self.group_key.is_empty() {
+            return format!("table {}", self.table);
+        }
+
+        self.group_key
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// Columns Flux adds to every table that only identify the stream, not the data
+const META_COLUMNS: [&str; 2] = ["result", "table"];
+
+/// Parses annotated CSV (`#group`, `#datatype`, `#default`) as returned by `/api/v2/query`.
+pub fn parse_annotated_csv(body: &str) -> Result<Vec<FluxTable>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+
+    let mut tables: Vec<FluxTable> = Vec::new();
+    let mut groups: Vec<bool> = Vec::new();
+    let mut datatypes: Vec<String> = Vec::new();
+    let mut defaults: Vec<String> = Vec::new();
+    let mut header: Option<Vec<String>> = None;
+
+    for record in reader.records() {
+        let record = record?;
+        let first = record.get(0).unwrap_or("");
+
+        if first.starts_with('#') {
+            // A new annotation block always precedes a new header
+            if header.is_some() {
+                header = None;
+                groups.clear();
+                datatypes.clear();
+                defaults.clear();
+            }
+            let values = record.iter().skip(1).map(String::from);
+            match first {
+                "#group" => groups = values.map(|v| v == "true").collect(),
+                "#datatype" => datatypes = values.collect(),
+                "#default" => defaults = values.collect(),
+                _ => {}
+            }
+            continue;
+        }
+
+        if record.iter().all(|f| f.is_empty()) {
+            header = None;
+            continue;
+        }
+
+        let Some(columns) = &header else {
+            let columns: Vec<String> = record.iter().skip(1).map(String::from).collect();
+            header = Some(columns);
+            continue;
+        };
+
+        let cells: Vec<&str> = record.iter().skip(1).collect();
+
+        if columns.iter().any(|c| c == "error") && columns.iter().all(|c| c == "error" || c == "reference") {
+            let idx = columns.iter().position(|c| c == "error").unwrap_or(0);
+            return Err(anyhow!("Flux error: {}", cells.get(idx).unwrap_or(&"")));
+        }
+
+        let cell = |name: &str| -> String {
+            columns
+                .iter()
+                .position(|c| c == name)
+                .map(|i| cell_or_default(&cells, &defaults, i))
+                .unwrap_or_default()
+        };
+        let result = cell("result");
+        let table = cell("table");
+
+        let is_new_table = tables
+            .last()
+            .map(|t| t.result != result || t.table != table || !same_columns(t, columns))
+            .unwrap_or(true);
+
+        let mut data_columns = Vec::new();
+        let mut row = Vec::new();
+        let mut group_key = Vec::new();
+
+        for (i, name) in columns.iter().enumerate() {
+            if META_COLUMNS.contains(&name.as_str()) {
+                continue;
+            }
+            let raw = cell_or_default(&cells, &defaults, i);
+            if is_new_table && groups.get(i).copied().unwrap_or(false) {
+                group_key.push((name.clone(), raw.clone()));
+            }
+            row.push(typed_value(&raw, datatypes.get(i).map(String::as_str).unwrap_or("string")));
+            data_columns.push(name.clone());
+        }
+
+        if is_new_table {
+            tables.push(FluxTable {
+                result,
+                table,
+                group_key,
+                columns: data_columns,
+                rows: Vec::new(),
+            });
+        }
+
+        if let Some(t) = tables.last_mut() {
+            t.rows.push(row);
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Unions all tables into one grid: the result name and table id first, then group-key columns, then the rest.
+/// Table ids restart in every result, so both are needed to tell tables apart.
+pub fn flatten(tables: &[FluxTable]) -> (Vec<String>, Vec<Vec<Value>>) {
+    let mut columns = vec!["result".to_string(), "table".to_string()];
+
+    for t in tables {
+        for (k, _) in &t.group_key {
+            if !columns.contains(k) {
+                columns.push(k.clone());
+            }
+        }
+    }
+    for t in tables {
+        for c in &t.columns {
+            if !columns.contains(c) {
+                columns.push(c.clone());
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for t in tables {
+        let positions: Vec<usize> = t
+            .columns
+            .iter()
+            .map(|c| columns.iter().position(|x| x == c).unwrap_or(0))
+            .collect();
+
+        for r in &t.rows {
+            let mut row = vec![Value::Null; columns.len()];
+            row[0] = Value::String(t.result.clone());
+            row[1] = Value::String(t.table.clone());
+            for (value, &pos) in r.iter().zip(&positions) {
+                row[pos] = value.clone();
+            }
+            rows.push(row);
+        }
+    }
+
+    (columns, rows)
+}
+
+fn same_columns(table: &FluxTable, header: &[String]) -> bool {
+    header
+        .iter()
+        .filter(|c| !META_COLUMNS.contains(&c.as_str()))
+        .eq(table.columns.iter())
+}
+
+fn cell_or_default(cells: &[&str], defaults: &[String], i: usize) -> String {
+    match cells.get(i) {
+        Some(v) if !v.is_empty() => v.to_string(),
+        _ => defaults.get(i).cloned().unwrap_or_default(),
+    }
+}
+
+fn typed_value(raw: &str, datatype: &str) -> Value {
+    if raw.is_empty() {
+        return Value::Null;
+    }
+
+    match datatype {
+        "long" => raw.parse::<i64>().map(Value::from).unwrap_or_else(|_| Value::String(raw.to_string())),
+        "unsignedLong" => raw.parse::<u64>().map(Value::from).unwrap_or_else(|_| Value::String(raw.to_string())),
+        "double" => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        "boolean" => Value::Bool(raw == "true"),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Two results, the first with two tables; `_result` comes from the #default row
+    const TWO_RESULTS: &str = "\
+#datatype,string,long,dateTime:RFC3339,double,string,string
+#group,false,false,false,false,true,true
+#default,_result,,,,,
+,result,table,_time,_value,_field,host
+,,0,2024-01-01T00:00:00Z,1.5,usage,a
+,,0,2024-01-01T00:01:00Z,,usage,a
+,,1,2024-01-01T00:00:00Z,3,usage,b
+
+#datatype,string,long,dateTime:RFC3339,long,boolean
+#group,false,false,false,false,true
+#default,max,,,,
+,result,table,_time,_value,up
+,,0,2024-01-01T00:00:00Z,7,true
+";
+
+    #[test]
+    fn parses_tables_and_results() {
+        let tables = parse_annotated_csv(TWO_RESULTS).unwrap();
+        let ids: Vec<(&str, &str)> = tables.iter().map(|t| (t.result.as_str(), t.table.as_str())).collect();
+        assert_eq!(ids, [("_result", "0"), ("_result", "1"), ("max", "0")]);
+
+        let first = &tables[0];
+        assert_eq!(first.columns, ["_time", "_value", "_field", "host"]);
+        assert_eq!(first.group_key, [("_field".to_string(), "usage".to_string()), ("host".to_string(), "a".to_string())]);
+        assert_eq!(first.group_key_label(), "_field=usage, host=a");
+        assert_eq!(first.rows, [
+            vec![json!("2024-01-01T00:00:00Z"), json!(1.5), json!("usage"), json!("a")],
+            vec![json!("2024-01-01T00:01:00Z"), Value::Null, json!("usage"), json!("a")],
+        ]);
+
+        assert_eq!(tables[1].rows[0][1], json!(3.0));
+        assert_eq!(tables[2].columns, ["_time", "_value", "up"]);
+        assert_eq!(tables[2].rows, [vec![json!("2024-01-01T00:00:00Z"), json!(7), json!(true)]]);
+    }
+
+    #[test]
+    fn new_annotations_start_a_new_result_without_blank_line() {
+        let body = TWO_RESULTS.replace("\n\n", "\n");
+        let tables = parse_annotated_csv(&body).unwrap();
+        assert_eq!(tables.len(), 3);
+        assert_eq!(tables[2].result, "max");
+        assert_eq!(tables[2].rows.len(), 1);
+    }
+
+    #[test]
+    fn error_table_is_an_error() {
+        let body = "\
+#datatype,string,string
+#group,true,true
+#default,,
+,error,reference
+,\"error calling function \"\"from\"\": bucket not found\",897
+";
+        let err = parse_annotated_csv(body).unwrap_err();
+        assert_eq!(err.to_string(), "Flux error: error calling function \"from\": bucket not found");
+    }
+
+    #[test]
+    fn flatten_keeps_results_apart() {
+        let tables = parse_annotated_csv(TWO_RESULTS).unwrap();
+        let (columns, rows) = flatten(&tables);
+        assert_eq!(columns, ["result", "table", "_field", "host", "up", "_time", "_value"]);
+        assert_eq!(rows.len(), 4);
+
+        let key = |row: &Vec<Value>| (row[0].clone(), row[1].clone());
+        assert_eq!(key(&rows[0]), (json!("_result"), json!("0")));
+        assert_eq!(key(&rows[2]), (json!("_result"), json!("1")));
+        assert_eq!(key(&rows[3]), (json!("max"), json!("0")));
+        assert_eq!(rows[3][4], json!(true));
+        assert_eq!(rows[3][2], Value::Null);
+    }
+}
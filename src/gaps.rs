@@ -0,0 +1,583 @@
+use anyhow::{anyhow, Result};
+use eframe::egui::{self, Color32};
+use std::sync::{Arc, Mutex};
+
+use crate::chart::{self, Line, Mark};
+use crate::copy::parse_time;
+use crate::influx::{self, quote_ident, InfluxClient, Series};
+use crate::stats::percentile;
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+// More buckets than this is almost certainly a typo in the interval
+const MAX_INTERVALS: i64 = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    Missing,
+    Low,
+    FlatLine,
+    Spike,
+}
+
+impl FindingKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FindingKind::Missing => "Missing",
+            FindingKind::Low => "Low count",
+            FindingKind::FlatLine => "Flat line",
+            FindingKind::Spike => "Spike",
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        match self {
+            FindingKind::Missing => Color32::from_rgb(220, 80, 80),
+            FindingKind::Low => Color32::from_rgb(220, 190, 60),
+            FindingKind::FlatLine => Color32::from_rgb(120, 110, 220),
+            FindingKind::Spike => Color32::from_rgb(200, 80, 160),
+        }
+    }
+}
+
+/// A suspicious span of one series, in unix seconds.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub series: String,
+    pub start: f64,
+    pub end: f64,
+    pub detail: String,
+}
+
+/// One interval of the field aggregate query.
+#[derive(Debug, Clone, Copy)]
+pub struct IntervalValue {
+    pub time: f64,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+pub struct GapView {
+    /// Connection the window was opened on
+    pub connection: u64,
+    pub database: String,
+    pub rp: Option<String>,
+    pub measurement: String,
+    /// Numeric field keys
+    pub fields: Vec<String>,
+    pub tags: Vec<String>,
+    pub field: String,
+    pub tag: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub interval: String,
+    /// Intervals below this share of the series' median count are reported
+    pub low_ratio: f64,
+    pub flat_intervals: usize,
+    /// Distance from the median, in MADs, that counts as a spike
+    pub spike_threshold: f64,
+    pub counts: Vec<Line>,
+    pub values: Vec<Line>,
+    pub findings: Vec<Finding>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl GapView {
+    pub fn new(connection: u64, database: String, rp: Option<String>, measurement: String) -> Self {
+        let day_ago = chrono::Utc::now() - chrono::Duration::hours(24);
+        Self {
+            connection,
+            database,
+            rp,
+            measurement,
+            fields: Vec::new(),
+            tags: Vec::new(),
+            field: String::new(),
+            tag: None,
+            start: day_ago.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            end: String::new(),
+            interval: "5m".to_string(),
+            low_ratio: 0.5,
+            flat_intervals: 6,
+            spike_threshold: 5.0,
+            counts: Vec::new(),
+            values: Vec::new(),
+            findings: Vec::new(),
+            loading: true,
+            error: None,
+        }
+    }
+
+    fn series_name(&self, series: &Series) -> String {
+        self.tag
+            .as_ref()
+            .and_then(|tag| series.tags.get(tag))
+            .and_then(|v| v.as_str())
+            .map(|v| if v.is_empty() { "(empty)".to_string() } else { v.to_string() })
+            .unwrap_or_else(|| "all".to_string())
+    }
+}
+
+/// Runs of empty and unusually sparse intervals. Intervals before the series' first point aren't gaps.
+pub fn count_findings(series: &str, counts: &[[f64; 2]], interval_secs: f64, low_ratio: f64) -> Vec<Finding> {
+    let mut nonzero: Vec<f64> = counts.iter().map(|[_, c]| *c).filter(|c| *c > 0.0).collect();
+    nonzero.sort_by(f64::total_cmp);
+    let Some(median) = percentile(&nonzero, 50.0) else {
+        return Vec::new();
+    };
+    let first = counts.iter().position(|[_, c]| *c > 0.0).unwrap_or(counts.len());
+
+    let mut findings: Vec<Finding> = Vec::new();
+    for [time, count] in &counts[first..] {
+        let kind = if *count == 0.0 {
+            FindingKind::Missing
+        } else if *count < median * low_ratio {
+            FindingKind::Low
+        } else {
+            continue;
+        };
+        match findings.last_mut() {
+            // Adjacent intervals of the same kind make one finding
+            Some(last) if last.kind == kind && (last.end - time).abs() < 1e-6 => last.end = time + interval_secs,
+            _ => findings.push(Finding {
+                kind,
+                series: series.to_string(),
+                start: *time,
+                end: time + interval_secs,
+                detail: String::new(),
+            }),
+        }
+    }
+
+    for finding in &mut findings {
+        let intervals = ((finding.end - finding.start) / interval_secs).round();
+        finding.detail = match finding.kind {
+            FindingKind::Missing => format!("no points for {} interval(s)", intervals),
+            _ => format!("{} interval(s) under {} points (median {})", intervals, chart::format_number(median * low_ratio), chart::format_number(median)),
+        };
+    }
+    findings
+}
+
+/// Flat lines (consecutive intervals holding one constant value) and spikes (min or max far from the median, in MADs).
+pub fn value_findings(
+    series: &str,
+    values: &[IntervalValue],
+    interval_secs: f64,
+    flat_intervals: usize,
+    spike_threshold: f64,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let mut run_start = 0;
+    for i in 1..=values.len() {
+        let continues = i < values.len() && {
+            let (prev, cur) = (&values[i - 1], &values[i]);
+            cur.min == cur.max
+                && prev.min == prev.max
+                && cur.min == prev.min
+                && (cur.time - prev.time - interval_secs).abs() < 1e-6
+        };
+        if continues {
+            continue;
+        }
+        let run = &values[run_start..i];
+        if run.len() >= flat_intervals.max(2) && run[0].min == run[0].max {
+            findings.push(Finding {
+                kind: FindingKind::FlatLine,
+                series: series.to_string(),
+                start: run[0].time,
+                end: run[run.len() - 1].time + interval_secs,
+                detail: format!("stuck at {} for {} intervals", chart::format_number(run[0].min), run.len()),
+            });
+        }
+        run_start = i;
+    }
+
+    let mut means: Vec<f64> = values.iter().map(|v| v.mean).collect();
+    means.sort_by(f64::total_cmp);
+    let Some(median) = percentile(&means, 50.0) else {
+        return findings;
+    };
+    let mut deviations: Vec<f64> = means.iter().map(|m| (m - median).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    // Scaled to match the standard deviation of normal data
+    let mad = percentile(&deviations, 50.0).unwrap_or(0.0) * 1.4826;
+    if mad > 0.0 {
+        for v in values {
+            let extreme = if (v.max - median).abs() >= (v.min - median).abs() { v.max } else { v.min };
+            let score = (extreme - median).abs() / mad;
+            if score > spike_threshold {
+                findings.push(Finding {
+                    kind: FindingKind::Spike,
+                    series: series.to_string(),
+                    start: v.time,
+                    end: v.time + interval_secs,
+                    detail: format!("{} is {:.1} MADs from the median {}", chart::format_number(extreme), score, chart::format_number(median)),
+                });
+            }
+        }
+    }
+    findings
+}
+
+fn marks(findings: &[Finding], kinds: &[FindingKind]) -> Vec<Mark> {
+    findings
+        .iter()
+        .filter(|f| kinds.contains(&f.kind))
+        .map(|f| Mark {
+            start: f.start,
+            end: f.end,
+            color: f.kind.color(),
+            label: format!("{} {}: {}", f.series, f.kind.label(), f.detail),
+        })
+        .collect()
+}
+
+impl InfluxDBApp {
+    pub(crate) fn open_gaps(&self, ctx: &egui::Context, state: &mut AppState, db: String, measurement: String) {
+        let (Some(id), Some(client)) = (state.active_connection, state.client()) else {
+            return;
+        };
+        let rp = state.selected_rp.clone();
+        state.gaps = Some(GapView::new(id, db.clone(), rp.clone(), measurement.clone()));
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+        self.runtime.spawn(async move {
+            let fields = client.show_field_keys(&db, rp.as_deref(), &measurement).await;
+            let tags = client.show_tag_keys(&db, &measurement).await;
+            {
+                let mut state = state_clone.lock().unwrap();
+                if let Some(view) = &mut state.gaps {
+                    view.loading = false;
+                    match (fields, tags) {
+                        (Ok(fields), Ok(tags)) => {
+                            let mut numeric: Vec<String> = Vec::new();
+                            for (name, field_type) in fields {
+                                if matches!(field_type.as_str(), "float" | "integer" | "unsigned") && !numeric.contains(&name) {
+                                    numeric.push(name);
+                                }
+                            }
+                            view.field = numeric.first().cloned().unwrap_or_default();
+                            view.fields = numeric;
+                            view.tags = tags;
+                        }
+                        (Err(e), _) | (_, Err(e)) => view.error = Some(format!("Error: {}", e)),
+                    }
+                }
+            }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    pub(crate) fn show_gaps_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.gaps.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(client) = state.connection_of(id).map(|c| c.client.clone()) else {
+            state.gaps = None;
+            return;
+        };
+        let Some(view) = &mut state.gaps else {
+            return;
+        };
+
+        let mut open = true;
+        let mut run = false;
+
+        egui::Window::new(format!("Gaps and anomalies: {} - {}", view.measurement, client.host()))
+            .id(egui::Id::new("gaps_window"))
+            .open(&mut open)
+            .default_size([760.0, 620.0])
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!view.loading, |ui| {
+                    egui::Grid::new("gaps_form").num_columns(2).show(ui, |ui| {
+                        ui.label("From:");
+                        ui.add(egui::TextEdit::singleline(&mut view.start).hint_text("RFC3339"));
+                        ui.end_row();
+
+                        ui.label("To:");
+                        ui.add(egui::TextEdit::singleline(&mut view.end).hint_text("now (RFC3339)"));
+                        ui.end_row();
+
+                        ui.label("Interval:");
+                        ui.add(egui::TextEdit::singleline(&mut view.interval).desired_width(80.0));
+                        ui.end_row();
+
+                        ui.label("Field:");
+                        egui::ComboBox::from_id_salt("gaps_field")
+                            .selected_text(&view.field)
+                            .show_ui(ui, |ui| {
+                                for field in &view.fields {
+                                    ui.selectable_value(&mut view.field, field.clone(), field);
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Group by tag:");
+                        egui::ComboBox::from_id_salt("gaps_tag")
+                            .selected_text(view.tag.as_deref().unwrap_or("(none)"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut view.tag, None, "(none)");
+                                for tag in &view.tags {
+                                    ui.selectable_value(&mut view.tag, Some(tag.clone()), tag);
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Low count below:");
+                        ui.add(egui::Slider::new(&mut view.low_ratio, 0.05..=0.95).custom_formatter(|v, _| format!("{:.0}% of median", v * 100.0)));
+                        ui.end_row();
+
+                        ui.label("Flat line after:");
+                        ui.add(egui::DragValue::new(&mut view.flat_intervals).range(2..=1000).suffix(" intervals"));
+                        ui.end_row();
+
+                        ui.label("Spike above:");
+                        ui.add(egui::DragValue::new(&mut view.spike_threshold).range(1.0..=100.0).speed(0.1).suffix(" MADs"));
+                        ui.end_row();
+                    });
+
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!view.field.is_empty(), egui::Button::new("Analyze")).clicked() {
+                            run = true;
+                        }
+                        if view.loading {
+                            ui.spinner();
+                        }
+                    });
+                });
+
+                if let Some(error) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if view.counts.is_empty() {
+                    return;
+                }
+
+                ui.separator();
+                ui.label("Points per interval");
+                chart::marked_line_chart(ui, &view.counts, &marks(&view.findings, &[FindingKind::Missing, FindingKind::Low]), 140.0);
+                ui.label(format!("Mean of {}", view.field));
+                chart::marked_line_chart(ui, &view.values, &marks(&view.findings, &[FindingKind::FlatLine, FindingKind::Spike]), 140.0);
+
+                ui.separator();
+                ui.label(format!("{} findings", view.findings.len()));
+                egui::ScrollArea::vertical().id_salt("gaps_findings").show(ui, |ui| {
+                    egui::Grid::new("gaps_findings_grid").striped(true).num_columns(5).show(ui, |ui| {
+                        for header in ["Kind", "Series", "From", "To", "Detail"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+
+                        for finding in &view.findings {
+                            ui.colored_label(finding.kind.color(), finding.kind.label());
+                            ui.label(&finding.series);
+                            ui.label(chart::format_time(finding.start));
+                            ui.label(chart::format_time(finding.end));
+                            ui.label(&finding.detail);
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        if !open {
+            state.gaps = None;
+            return;
+        }
+        if !run {
+            return;
+        }
+        view.loading = true;
+        view.error = None;
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = Self::analyze_gaps(&state_clone, &client).await;
+            {
+                let mut state = state_clone.lock().unwrap();
+                if let Some(view) = &mut state.gaps {
+                    view.loading = false;
+                    if let Err(e) = result {
+                        view.error = Some(format!("Error: {}", e));
+                    }
+                }
+            }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    async fn analyze_gaps(state: &Arc<Mutex<AppState>>, client: &InfluxClient) -> Result<()> {
+        let (database, count_query, value_query, settings) = {
+            let state = state.lock().unwrap();
+            let view = state.gaps.as_ref().ok_or_else(|| anyhow!("Closed"))?;
+
+            let start = parse_time(&view.start)?.ok_or_else(|| anyhow!("Enter a start time"))?;
+            let end = parse_time(&view.end)?.unwrap_or_else(|| chrono::Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX));
+            let interval = influx::parse_duration(&view.interval)
+                .filter(|i| *i > 0)
+                .ok_or_else(|| anyhow!("Invalid interval '{}'", view.interval))?;
+            if end <= start {
+                return Err(anyhow!("The range is empty"));
+            }
+            if (end - start) / interval > MAX_INTERVALS {
+                return Err(anyhow!("More than {} intervals; use a longer interval", MAX_INTERVALS));
+            }
+
+            let from = influx::qualified_measurement(&view.database, view.rp.as_deref(), &view.measurement);
+            let mut group_by = format!("time({})", view.interval.trim());
+            if let Some(tag) = &view.tag {
+                group_by.push_str(&format!(", {}", quote_ident(tag)));
+            }
+            let field = quote_ident(&view.field);
+            let condition = format!("time >= {} AND time < {}", start, end);
+            (
+                view.database.clone(),
+                // Empty intervals are what we're after, so they're filled with zero counts
+                format!("SELECT COUNT({}) FROM {} WHERE {} GROUP BY {} fill(0)", field, from, condition, group_by),
+                format!(
+                    "SELECT MEAN({f}), MIN({f}), MAX({f}) FROM {} WHERE {} GROUP BY {} fill(none)",
+                    from,
+                    condition,
+                    group_by,
+                    f = field
+                ),
+                (interval as f64 / 1e9, view.low_ratio, view.flat_intervals, view.spike_threshold),
+            )
+        };
+        let (interval_secs, low_ratio, flat_intervals, spike_threshold) = settings;
+
+        let count_series = client.query_series(&count_query, Some(&database)).await?;
+        let value_series = client.query_series(&value_query, Some(&database)).await?;
+
+        let mut state = state.lock().unwrap();
+        let view = state.gaps.as_mut().ok_or_else(|| anyhow!("Closed"))?;
+        let mut findings = Vec::new();
+
+        view.counts = count_series
+            .iter()
+            .map(|series| {
+                let name = view.series_name(series);
+                let points: Vec<[f64; 2]> = series
+                    .values
+                    .iter()
+                    .filter_map(|row| Some([chart::time_secs(row.first()?)?, row.get(1)?.as_f64()?]))
+                    .collect();
+                findings.extend(count_findings(&name, &points, interval_secs, low_ratio));
+                Line { name, points }
+            })
+            .collect();
+
+        view.values = value_series
+            .iter()
+            .map(|series| {
+                let name = view.series_name(series);
+                let values: Vec<IntervalValue> = series
+                    .values
+                    .iter()
+                    .filter_map(|row| {
+                        Some(IntervalValue {
+                            time: chart::time_secs(row.first()?)?,
+                            mean: row.get(1)?.as_f64()?,
+                            min: row.get(2)?.as_f64()?,
+                            max: row.get(3)?.as_f64()?,
+                        })
+                    })
+                    .collect();
+                findings.extend(value_findings(&name, &values, interval_secs, flat_intervals, spike_threshold));
+                Line {
+                    name,
+                    points: values.iter().map(|v| [v.time, v.mean]).collect(),
+                }
+            })
+            .collect();
+
+        findings.sort_by(|a, b| a.start.total_cmp(&b.start));
+        view.findings = findings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: f64 = 60.0;
+
+    fn counts(values: &[f64]) -> Vec<[f64; 2]> {
+        values.iter().enumerate().map(|(i, c)| [i as f64 * INTERVAL, *c]).collect()
+    }
+
+    fn values(values: &[f64]) -> Vec<IntervalValue> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| IntervalValue { time: i as f64 * INTERVAL, mean: *v, min: *v, max: *v })
+            .collect()
+    }
+
+    fn spans(findings: &[Finding]) -> Vec<(FindingKind, f64, f64)> {
+        findings.iter().map(|f| (f.kind, f.start, f.end)).collect()
+    }
+
+    #[test]
+    fn gap_in_the_middle_is_one_finding() {
+        let findings = count_findings("all", &counts(&[10.0, 10.0, 0.0, 0.0, 10.0]), INTERVAL, 0.5);
+        assert_eq!(spans(&findings), vec![(FindingKind::Missing, 120.0, 240.0)]);
+        assert_eq!(findings[0].detail, "no points for 2 interval(s)");
+    }
+
+    #[test]
+    fn trailing_gap_is_reported() {
+        let findings = count_findings("all", &counts(&[10.0, 10.0, 10.0, 0.0]), INTERVAL, 0.5);
+        assert_eq!(spans(&findings), vec![(FindingKind::Missing, 180.0, 240.0)]);
+    }
+
+    #[test]
+    fn leading_zeros_are_ignored() {
+        assert!(count_findings("all", &counts(&[0.0, 0.0, 10.0, 10.0]), INTERVAL, 0.5).is_empty());
+        assert!(count_findings("all", &counts(&[0.0, 0.0]), INTERVAL, 0.5).is_empty());
+    }
+
+    #[test]
+    fn sparse_intervals_are_low() {
+        let findings = count_findings("all", &counts(&[10.0, 10.0, 2.0, 0.0, 10.0]), INTERVAL, 0.5);
+        assert_eq!(
+            spans(&findings),
+            vec![(FindingKind::Low, 120.0, 180.0), (FindingKind::Missing, 180.0, 240.0)]
+        );
+    }
+
+    #[test]
+    fn flat_runs_need_flat_intervals() {
+        let short = values(&[1.0, 2.0, 5.0, 5.0, 5.0, 3.0]);
+        assert!(value_findings("all", &short, INTERVAL, 4, f64::MAX).is_empty());
+
+        let long = values(&[1.0, 2.0, 5.0, 5.0, 5.0, 5.0, 3.0]);
+        let findings = value_findings("all", &long, INTERVAL, 4, f64::MAX);
+        assert_eq!(spans(&findings), vec![(FindingKind::FlatLine, 120.0, 360.0)]);
+        assert_eq!(findings[0].detail, "stuck at 5 for 4 intervals");
+    }
+
+    #[test]
+    fn spikes_depend_on_the_mad_threshold() {
+        let data = values(&[10.0, 11.0, 10.0, 11.0, 10.0, 11.0, 10.0, 11.0, 50.0]);
+        // Median 11, MAD 1 * 1.4826, so 50 is about 26 MADs out
+        let findings = value_findings("all", &data, INTERVAL, 100, 5.0);
+        assert_eq!(spans(&findings), vec![(FindingKind::Spike, 480.0, 540.0)]);
+        assert!(value_findings("all", &data, INTERVAL, 100, 30.0).is_empty());
+    }
+
+    #[test]
+    fn constant_series_has_no_spikes() {
+        // Half the means equal the median, so the MAD is 0
+        let data = values(&[10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 50.0]);
+        assert!(value_findings("all", &data, INTERVAL, 100, 5.0).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, TextFormat};
+
+use crate::ui::QueryLanguage;
+
+const INFLUXQL_KEYWORDS: &[&str] = &[
+    "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "BEGIN", "BY", "CREATE", "CONTINUOUS", "DATABASE",
+    "DATABASES", "DEFAULT", "DELETE", "DESC", "DESTINATIONS", "DIAGNOSTICS", "DISTINCT", "DROP",
+    "DURATION", "END", "EVERY", "EXPLAIN", "ANALYZE", "FIELD", "FOR", "FROM", "GRANT", "GRANTS",
+    "GROUP", "GROUPS", "IN", "INF", "INSERT", "INTO", "KEY", "KEYS", "KILL", "LIMIT", "MEASUREMENT",
+    "MEASUREMENTS", "NAME", "OFFSET", "ON", "OR", "ORDER", "PASSWORD", "POLICY", "POLICIES",
+    "PRIVILEGES", "QUERIES", "QUERY", "READ", "REPLICATION", "RESAMPLE", "RETENTION", "REVOKE",
+    "SELECT", "SERIES", "SET", "SHARD", "SHARDS", "SHOW", "SLIMIT", "SOFFSET", "STATS",
+    "SUBSCRIPTION", "SUBSCRIPTIONS", "TAG", "TO", "USER", "USERS", "VALUES", "WHERE", "WITH",
+    "WRITE", "FILL", "TZ", "CARDINALITY", "EXACT", "NOT", "NULL", "TIME", "NOW",
+];
+
+const SQL_KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "AND", "OR", "NOT", "IN", "IS", "NULL", "AS", "ON", "JOIN", "INNER",
+    "LEFT", "RIGHT", "FULL", "OUTER", "CROSS", "GROUP", "BY", "ORDER", "ASC", "DESC", "LIMIT",
+    "OFFSET", "HAVING", "DISTINCT", "UNION", "ALL", "CASE", "WHEN", "THEN", "ELSE", "END", "WITH",
+    "BETWEEN", "LIKE", "ILIKE", "INTERVAL", "SHOW", "TABLES", "COLUMNS", "DESCRIBE", "EXPLAIN",
+    "ANALYZE", "OVER", "PARTITION", "CAST", "TRUE", "FALSE", "EXISTS", "VALUES", "NOW",
+];
+
+const FLUX_KEYWORDS: &[&str] = &[
+    "import", "package", "option", "builtin", "testcase", "return", "if", "then", "else", "and",
+    "or", "not", "exists", "true", "false",
+];
+
+/// Colors InfluxQL/Flux/SQL source for the query editor.
+pub fn layout_job(ui: &egui::Ui, code: &str, language: QueryLanguage) -> LayoutJob {
+    let dark = ui.visuals().dark_mode;
+    let font = FontId::monospace(egui::TextStyle::Monospace.resolve(ui.style()).size);
+    let plain = ui.visuals().text_color();
+
+    let (keyword, string, number, comment, function) = if dark {
+        (
+            Color32::from_rgb(86, 156, 214),
+            Color32::from_rgb(206, 145, 120),
+            Color32::from_rgb(181, 206, 168),
+            Color32::from_rgb(106, 153, 85),
+            Color32::from_rgb(220, 220, 170),
+        )
+    } else {
+        (
+            Color32::from_rgb(0, 0, 200),
+            Color32::from_rgb(163, 21, 21),
+            Color32::from_rgb(9, 134, 88),
+            Color32::from_rgb(0, 128, 0),
+            Color32::from_rgb(121, 94, 38),
+        )
+    };
+
+    let mut job = LayoutJob::default();
+    let mut append = |text: &str, color: Color32| {
+        job.append(text, 0.0, TextFormat::simple(font.clone(), color));
+    };
+
+    let chars: Vec<(usize, char)> = code.char_indices().collect();
+    let slice = |from: usize, to: usize| -> &str {
+        let start = chars.get(from).map(|c| c.0).unwrap_or(code.len());
+        let end = chars.get(to).map(|c| c.0).unwrap_or(code.len());
+        &code[start..end]
+    };
+
+    let line_comment = match language {
+        QueryLanguage::Flux => "//",
+        QueryLanguage::InfluxQL | QueryLanguage::Sql => "--",
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let start = i;
+
+        if code[chars[i].0..].starts_with(line_comment) {
+            while i < chars.len() && chars[i].1 != '\n' {
+                i += 1;
+            }
+            append(slice(start, i), comment);
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i].1 != c {
+                if chars[i].1 == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            // InfluxQL and SQL double quotes are identifiers, not strings
+            let color = if language != QueryLanguage::Flux && c == '"' { plain } else { string };
+            append(slice(start, i), color);
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '.') {
+                i += 1;
+            }
+            append(slice(start, i), number);
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                i += 1;
+            }
+            let word = slice(start, i);
+            let is_keyword = match language {
+                QueryLanguage::Flux => FLUX_KEYWORDS.contains(&word),
+                QueryLanguage::InfluxQL => INFLUXQL_KEYWORDS.contains(&word.to_ascii_uppercase().as_str()),
+                QueryLanguage::Sql => SQL_KEYWORDS.contains(&word.to_ascii_uppercase().as_str()),
+            };
+            let is_call = chars.get(i).map(|c| c.1 == '(').unwrap_or(false);
+
+            let color = if is_keyword {
+                keyword
+            } else if is_call {
+                function
+            } else {
+                plain
+            };
+            append(word, color);
+        } else if language == QueryLanguage::Flux && code[chars[i].0..].starts_with("|>") {
+            i += 2;
+            append(slice(start, i), keyword);
+        } else {
+            i += 1;
+            append(slice(start, i), plain);
+        }
+    }
+
+    job
+}
@@ -1,13 +1,178 @@
 use anyhow::{anyhow, Result};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::credentials::Credentials;
+use crate::flux::{self, FluxTable};
+use crate::server::ServerInfo;
+
+// Recent InfluxQL run for the user, normalized, so the running queries monitor can point out our own.
+// Metadata lookups and polling aren't recorded, they would push the long-running queries out.
+static ISSUED: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+const ISSUED_MAX: usize = 200;
+
+/// Query text without identifier quotes, case or layout, since `SHOW QUERIES` lists it as re-formatted by InfluxDB.
+fn normalize_query(query: &str) -> String {
+    query
+        .replace('"', "")
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Remembers a user query, page load or job statement for `issued_by_app`.
+pub fn record_issued(query: &str) {
+    let mut issued = ISSUED.lock().unwrap();
+    if issued.len() == ISSUED_MAX {
+        issued.pop_front();
+    }
+    issued.push_back(normalize_query(query));
+}
+
+/// Whether a query text from `SHOW QUERIES` was sent by this app.
+pub fn issued_by_app(query: &str) -> bool {
+    let query = normalize_query(query);
+    ISSUED.lock().unwrap().iter().any(|q| *q == query)
+}
 
 #[derive(Clone)]
 pub struct InfluxClient {
     client: Arc<Client>,
     base_url: String,
+    credentials: Option<Credentials>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SqlFormat {
+    Json,
+    Jsonl,
+}
+
+impl SqlFormat {
+    pub const ALL: [SqlFormat; 2] = [SqlFormat::Json, SqlFormat::Jsonl];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SqlFormat::Json => "JSON",
+            SqlFormat::Jsonl => "JSONL",
+        }
+    }
+
+    fn param(&self) -> &'static str {
+        match self {
+            SqlFormat::Json => "json",
+            SqlFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub name: String,
+    pub duration: String,
+    pub shard_group_duration: String,
+    pub replication: u32,
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContinuousQuery {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Privilege {
+    Read,
+    Write,
+    All,
+}
+
+impl Privilege {
+    pub const ALL: [Privilege; 3] = [Privilege::Read, Privilege::Write, Privilege::All];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Privilege::Read => "READ",
+            Privilege::Write => "WRITE",
+            Privilege::All => "ALL",
+        }
+    }
+
+    fn bits(privilege: Option<Privilege>) -> u8 {
+        match privilege {
+            None => 0,
+            Some(Privilege::Read) => 1,
+            Some(Privilege::Write) => 2,
+            Some(Privilege::All) => 3,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Option<Privilege> {
+        match bits {
+            1 => Some(Privilege::Read),
+            2 => Some(Privilege::Write),
+            3 => Some(Privilege::All),
+            _ => None,
+        }
+    }
+
+    /// What to grant and then revoke to go from `from` to `to`. InfluxDB adds grants together, so granting
+    /// first means a failure never leaves the user with less than both privileges have in common.
+    pub fn changes(from: Option<Privilege>, to: Option<Privilege>) -> (Option<Privilege>, Option<Privilege>) {
+        let (from, to) = (Self::bits(from), Self::bits(to));
+        (Self::from_bits(to & !from), Self::from_bits(from & !to))
+    }
+
+    /// Reads the privilege column of `SHOW GRANTS`, where no access is `NO PRIVILEGES`.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "READ" => Some(Privilege::Read),
+            "WRITE" => Some(Privilege::Write),
+            "ALL PRIVILEGES" | "ALL" => Some(Privilege::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UserInfo {
+    pub name: String,
+    pub admin: bool,
+}
+
+#[derive(Debug, Clone)]
+/// A shard from `SHOW SHARDS`; times and RP come from its group.
+pub struct Shard {
+    pub id: u64,
+    pub shard_group: u64,
+    pub owners: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShardGroup {
+    pub id: u64,
+    pub database: String,
+    pub retention_policy: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub expiry_time: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunningQuery {
+    pub id: u64,
+    pub query: String,
+    pub database: String,
+    pub duration: String,
+    pub duration_ns: i64,
+    pub status: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -23,11 +188,15 @@ struct QueryResult {
     error: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Series {
-    name: Option<String>,
-    columns: Vec<String>,
-    values: Vec<Vec<Value>>,
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Series {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tags: serde_json::Map<String, Value>,
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub values: Vec<Vec<Value>>,
 }
 
 impl InfluxClient {
@@ -47,13 +216,90 @@ impl InfluxClient {
         Self {
             client: Arc::new(client),
             base_url: format!("http://{}", host),
+            credentials: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn host(&self) -> &str {
+        self.base_url.trim_start_matches("http://")
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.authorize(self.client.get(url))
+    }
+
+    fn post(&self, url: &str) -> RequestBuilder {
+        self.authorize(self.client.post(url))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.credentials {
+            Some(Credentials::Password { username, password }) => request.basic_auth(username, Some(password)),
+            Some(Credentials::Token(token)) => request.header("Authorization", format!("Token {}", token)),
+            None => request,
+        }
+    }
+
+    pub async fn ping(&self) -> Result<ServerInfo> {
+        let url = format!("{}/ping", self.base_url);
+
+        let response = self.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
         }
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let version = header("X-Influxdb-Version");
+        let build = header("X-Influxdb-Build");
+
+        // InfluxDB 3.x also reports its version in a JSON body
+        let version = match version {
+            Some(v) => Some(v),
+            None => response
+                .json::<Value>()
+                .await
+                .ok()
+                .and_then(|body| body.get("version").and_then(|v| v.as_str()).map(String::from)),
+        };
+
+        Ok(ServerInfo::from_ping(version.as_deref(), build.as_deref()))
     }
 
     pub async fn query(
         &self,
         query: &str,
         database: Option<&str>,
+    ) -> Result<Option<(Vec<String>, Vec<Vec<Value>>)>> {
+        self.run_query(query, database, None).await
+    }
+
+    /// Like `query`, but with timestamps as integers in the given precision (`ns`, `ms`, ...).
+    pub async fn query_epoch(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        epoch: &str,
+    ) -> Result<Option<(Vec<String>, Vec<Vec<Value>>)>> {
+        self.run_query(query, database, Some(epoch)).await
+    }
+
+    async fn run_query(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        epoch: Option<&str>,
     ) -> Result<Option<(Vec<String>, Vec<Vec<Value>>)>> {
         let url = format!("{}/query", self.base_url);
         let mut params = vec![("q", query.to_string())];
@@ -61,9 +307,11 @@ impl InfluxClient {
         if let Some(db) = database {
             params.push(("db", db.to_string()));
         }
+        if let Some(epoch) = epoch {
+            params.push(("epoch", epoch.to_string()));
+        }
 
         let response = self
-            .client
             .get(&url)
             .query(&params)
             .send()
@@ -88,6 +336,222 @@ impl InfluxClient {
         Ok(None)
     }
 
+    /// Writes line protocol with nanosecond timestamps.
+    pub async fn write(&self, database: &str, rp: Option<&str>, lines: String) -> Result<()> {
+        let url = format!("{}/write", self.base_url);
+        let mut params = vec![("db", database), ("precision", "ns")];
+
+        if let Some(rp) = rp {
+            params.push(("rp", rp));
+        }
+
+        let response = self
+            .post(&url)
+            .query(&params)
+            .body(lines)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        Ok(())
+    }
+
+    /// Runs a statement that changes server state; InfluxDB only accepts those over POST.
+    pub async fn execute(&self, statement: &str, database: Option<&str>) -> Result<()> {
+        self.post_query(statement, database).await.map(|_| ())
+    }
+
+    /// Runs a `SELECT ... INTO` and returns the number of points written.
+    pub async fn select_into(&self, statement: &str, database: Option<&str>) -> Result<u64> {
+        record_issued(statement);
+        let response = self.post_query(statement, database).await?;
+        Ok(response.results.iter().map(|r| sum_counts(&r.series)).sum())
+    }
+
+    async fn post_query(&self, statement: &str, database: Option<&str>) -> Result<InfluxResponse> {
+        let url = format!("{}/query", self.base_url);
+        let mut params = vec![("q", statement.to_string())];
+
+        if let Some(db) = database {
+            params.push(("db", db.to_string()));
+        }
+
+        let response = self
+            .post(&url)
+            .form(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        let influx_response: InfluxResponse = response.json().await?;
+
+        if let Some(err) = influx_response.results.iter().find_map(|r| r.error.as_ref()) {
+            return Err(anyhow!("InfluxDB error: {}", err));
+        }
+
+        Ok(influx_response)
+    }
+
+    /// Asks `/api/v2/query` for the cheapest Flux query; `None` when the answer doesn't tell (auth, proxies, ...).
+    pub async fn flux_enabled(&self) -> Option<bool> {
+        let url = format!("{}/api/v2/query", self.base_url);
+        let body = json!({ "query": "buckets() |> limit(n: 1)", "type": "flux" });
+
+        let response = self.post(&url).json(&body).send().await.ok()?;
+        let status = response.status();
+        if status.is_success() {
+            return Some(true);
+        }
+
+        // 1.x answers 403 "Flux query service disabled. Verify flux-enabled=true ..."
+        let text = response.text().await.unwrap_or_default();
+        if status == reqwest::StatusCode::FORBIDDEN && text.contains("flux-enabled") {
+            return Some(false);
+        }
+        None
+    }
+
+    pub async fn query_flux(&self, query: &str) -> Result<Vec<FluxTable>> {
+        let url = format!("{}/api/v2/query", self.base_url);
+        let body = json!({
+            "query": query,
+            "type": "flux",
+            "dialect": {
+                "header": true,
+                "annotations": ["group", "datatype", "default"],
+            },
+        });
+
+        let response = self
+            .post(&url)
+            .header("Accept", "application/csv")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        flux::parse_annotated_csv(&response.text().await?)
+    }
+
+    /// Runs SQL against InfluxDB 3.x, which returns rows as JSON objects keyed by column.
+    pub async fn query_sql(
+        &self,
+        query: &str,
+        database: &str,
+        format: SqlFormat,
+    ) -> Result<Option<(Vec<String>, Vec<Vec<Value>>)>> {
+        let url = format!("{}/api/v3/query_sql", self.base_url);
+        let params = [("db", database), ("q", query), ("format", format.param())];
+
+        let response = self
+            .get(&url)
+            .query(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        let body = response.text().await?;
+        let objects: Vec<serde_json::Map<String, Value>> = match format {
+            SqlFormat::Json => serde_json::from_str(&body)?,
+            SqlFormat::Jsonl => body
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<serde_json::Result<_>>()?,
+        };
+
+        if objects.is_empty() {
+            return Ok(None);
+        }
+
+        // Nulls are omitted from the objects, so collect columns across all rows
+        let mut columns: Vec<String> = Vec::new();
+        for obj in &objects {
+            for key in obj.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+
+        let rows = objects
+            .into_iter()
+            .map(|mut obj| {
+                columns
+                    .iter()
+                    .map(|c| obj.remove(c).unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Some((columns, rows)))
+    }
+
+    pub async fn show_databases_v3(&self) -> Result<Vec<String>> {
+        let url = format!("{}/api/v3/configure/database", self.base_url);
+
+        let response = self
+            .get(&url)
+            .query(&[("format", "json")])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        let objects: Vec<serde_json::Map<String, Value>> = response.json().await?;
+
+        Ok(objects
+            .into_iter()
+            .filter_map(|obj| {
+                obj.get("iox::database")
+                    .and_then(|v| v.as_str().map(String::from))
+            })
+            .filter(|db| db != "_internal")
+            .collect())
+    }
+
+    /// Like `query`, but keeps every series of the first result (GROUP BY, per-measurement SHOW output).
+    pub async fn query_series(&self, query: &str, database: Option<&str>) -> Result<Vec<Series>> {
+        let url = format!("{}/query", self.base_url);
+        let mut params = vec![("q", query.to_string())];
+
+        if let Some(db) = database {
+            params.push(("db", db.to_string()));
+        }
+
+        let response = self
+            .get(&url)
+            .query(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), response.text().await?));
+        }
+
+        let influx_response: InfluxResponse = response.json().await?;
+
+        match influx_response.results.into_iter().next() {
+            Some(QueryResult { error: Some(err), .. }) => Err(anyhow!("InfluxDB error: {}", err)),
+            Some(result) => Ok(result.series),
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub async fn show_databases(&self) -> Result<Vec<String>> {
         let result = self.query("SHOW DATABASES", None).await?;
 
@@ -117,4 +581,721 @@ impl InfluxClient {
             Ok(Vec::new())
         }
     }
-}
\ No newline at end of file
+
+    pub async fn show_retention_policies(&self, database: &str) -> Result<Vec<RetentionPolicy>> {
+        let query = format!("SHOW RETENTION POLICIES ON {}", quote_ident(database));
+        let result = self.query(&query, None).await?;
+
+        let Some((cols, rows)) = result else {
+            return Ok(Vec::new());
+        };
+
+        let idx = |name: &str| cols.iter().position(|c| c == name);
+        let (name, duration, shard, replication, default) = (
+            idx("name"),
+            idx("duration"),
+            idx("shardGroupDuration"),
+            idx("replicaN"),
+            idx("default"),
+        );
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let text = |i: Option<usize>| {
+                    i.and_then(|i| row.get(i))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                RetentionPolicy {
+                    name: text(name),
+                    duration: text(duration),
+                    shard_group_duration: text(shard),
+                    replication: replication
+                        .and_then(|i| row.get(i))
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(1) as u32,
+                    default: default
+                        .and_then(|i| row.get(i))
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                }
+            })
+            .collect())
+    }
+
+    /// Field keys with their types; a field can be listed once per type it has across shards.
+    pub async fn show_field_keys(
+        &self,
+        database: &str,
+        rp: Option<&str>,
+        measurement: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let query = format!(
+            "SHOW FIELD KEYS FROM {}",
+            qualified_measurement(database, rp, measurement)
+        );
+        let result = self.query(&query, Some(database)).await?;
+
+        Ok(result
+            .map(|(_, rows)| {
+                rows.into_iter()
+                    .filter_map(|row| {
+                        let name = row.first()?.as_str()?.to_string();
+                        let field_type = row.get(1).and_then(|v| v.as_str()).unwrap_or("").to_string();
+                        Some((name, field_type))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Field keys and types of every measurement in the database.
+    pub async fn show_all_field_keys(&self, database: &str) -> Result<Vec<(String, Vec<(String, String)>)>> {
+        let series = self.query_series("SHOW FIELD KEYS", Some(database)).await?;
+
+        Ok(series
+            .into_iter()
+            .map(|s| {
+                let fields = s
+                    .values
+                    .iter()
+                    .filter_map(|row| {
+                        let name = row.first()?.as_str()?.to_string();
+                        let field_type = row.get(1).and_then(|v| v.as_str()).unwrap_or("").to_string();
+                        Some((name, field_type))
+                    })
+                    .collect();
+                (s.name.unwrap_or_default(), fields)
+            })
+            .collect())
+    }
+
+    /// Tag keys of every measurement in the database.
+    pub async fn show_all_tag_keys(&self, database: &str) -> Result<Vec<(String, Vec<String>)>> {
+        let series = self.query_series("SHOW TAG KEYS", Some(database)).await?;
+
+        Ok(series
+            .into_iter()
+            .map(|s| {
+                let keys = s
+                    .values
+                    .iter()
+                    .filter_map(|row| row.first().and_then(|v| v.as_str().map(String::from)))
+                    .collect();
+                (s.name.unwrap_or_default(), keys)
+            })
+            .collect())
+    }
+
+    pub async fn show_tag_keys(&self, database: &str, measurement: &str) -> Result<Vec<String>> {
+        let query = format!("SHOW TAG KEYS FROM {}", quote_ident(measurement));
+        let result = self.query(&query, Some(database)).await?;
+
+        Ok(result
+            .map(|(_, rows)| {
+                rows.into_iter()
+                    .filter_map(|row| row.first().and_then(|v| v.as_str().map(String::from)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Series keys of a measurement, optionally narrowed by a tag predicate.
+    pub async fn show_series(
+        &self,
+        database: &str,
+        measurement: &str,
+        predicate: Option<&str>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<String>> {
+        let mut query = format!("SHOW SERIES FROM {}", quote_ident(measurement));
+        if let Some(predicate) = predicate.map(str::trim).filter(|p| !p.is_empty()) {
+            query.push_str(&format!(" WHERE {}", predicate));
+        }
+        query.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset));
+
+        let result = self.query(&query, Some(database)).await?;
+
+        Ok(result
+            .map(|(_, rows)| {
+                rows.into_iter()
+                    .filter_map(|row| row.first().and_then(|v| v.as_str().map(String::from)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Nanosecond timestamp of the oldest point, if the measurement has any data.
+    pub async fn first_timestamp(&self, database: &str, rp: Option<&str>, measurement: &str) -> Result<Option<i64>> {
+        let query = format!(
+            "SELECT * FROM {} ORDER BY time ASC LIMIT 1",
+            qualified_measurement(database, rp, measurement)
+        );
+        let result = self.query_epoch(&query, Some(database), "ns").await?;
+
+        Ok(result.and_then(|(_, rows)| rows.first().and_then(|r| r.first()).and_then(|v| v.as_i64())))
+    }
+
+    pub async fn series_cardinality(&self, database: &str, exact: bool) -> Result<u64> {
+        let query = format!("SHOW SERIES {}CARDINALITY", exact_keyword(exact));
+        // Exact counts come back as one series per measurement
+        Ok(sum_counts(&self.query_series(&query, Some(database)).await?))
+    }
+
+    pub async fn measurement_cardinality(&self, database: &str, exact: bool) -> Result<u64> {
+        let query = format!("SHOW MEASUREMENT {}CARDINALITY", exact_keyword(exact));
+        Ok(sum_counts(&self.query_series(&query, Some(database)).await?))
+    }
+
+    /// Series count of each measurement, largest first.
+    pub async fn series_cardinality_by_measurement(
+        &self,
+        database: &str,
+        measurements: &[String],
+        exact: bool,
+    ) -> Result<Vec<(String, u64)>> {
+        let mut counts = Vec::new();
+
+        if exact {
+            for series in self.query_series("SHOW SERIES EXACT CARDINALITY", Some(database)).await? {
+                let name = series.name.clone().unwrap_or_default();
+                counts.push((name, sum_counts(std::slice::from_ref(&series))));
+            }
+        } else {
+            for measurement in measurements {
+                let query = format!("SHOW SERIES CARDINALITY FROM {}", quote_ident(measurement));
+                let count = sum_counts(&self.query_series(&query, Some(database)).await?);
+                counts.push((measurement.clone(), count));
+            }
+        }
+
+        counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+        Ok(counts)
+    }
+
+    pub async fn tag_key_cardinality(&self, database: &str, measurement: &str, exact: bool) -> Result<u64> {
+        let query = format!(
+            "SHOW TAG KEY {}CARDINALITY FROM {}",
+            exact_keyword(exact),
+            quote_ident(measurement)
+        );
+        Ok(sum_counts(&self.query_series(&query, Some(database)).await?))
+    }
+
+    pub async fn tag_values_cardinality(
+        &self,
+        database: &str,
+        measurement: &str,
+        key: &str,
+        exact: bool,
+    ) -> Result<u64> {
+        let query = format!(
+            "SHOW TAG VALUES {}CARDINALITY FROM {} WITH KEY = {}",
+            exact_keyword(exact),
+            quote_ident(measurement),
+            quote_ident(key)
+        );
+        Ok(sum_counts(&self.query_series(&query, Some(database)).await?))
+    }
+
+    pub async fn create_retention_policy(&self, database: &str, rp: &RetentionPolicy) -> Result<()> {
+        let statement = format!(
+            "CREATE RETENTION POLICY {} ON {}{}",
+            quote_ident(&rp.name),
+            quote_ident(database),
+            retention_policy_options(rp)
+        );
+        self.execute(&statement, None).await
+    }
+
+    pub async fn alter_retention_policy(&self, database: &str, rp: &RetentionPolicy) -> Result<()> {
+        let statement = format!(
+            "ALTER RETENTION POLICY {} ON {}{}",
+            quote_ident(&rp.name),
+            quote_ident(database),
+            retention_policy_options(rp)
+        );
+        self.execute(&statement, None).await
+    }
+
+    pub async fn create_database(&self, name: &str, rp: Option<&RetentionPolicy>) -> Result<()> {
+        let mut statement = format!("CREATE DATABASE {}", quote_ident(name));
+
+        if let Some(rp) = rp {
+            statement.push_str(" WITH");
+            let options = retention_policy_options(&RetentionPolicy {
+                default: false,
+                ..rp.clone()
+            });
+            statement.push_str(&options);
+            if !rp.name.trim().is_empty() {
+                statement.push_str(&format!(" NAME {}", quote_ident(&rp.name)));
+            }
+        }
+
+        self.execute(&statement, None).await
+    }
+
+    pub async fn drop_database(&self, name: &str) -> Result<()> {
+        self.execute(&format!("DROP DATABASE {}", quote_ident(name)), None).await
+    }
+
+    pub async fn drop_measurement(&self, database: &str, measurement: &str) -> Result<()> {
+        let statement = format!("DROP MEASUREMENT {}", quote_ident(measurement));
+        self.execute(&statement, Some(database)).await
+    }
+
+    pub async fn drop_series(&self, database: &str, measurement: &str, predicate: &str) -> Result<()> {
+        let mut statement = format!("DROP SERIES FROM {}", quote_ident(measurement));
+        if !predicate.trim().is_empty() {
+            statement.push_str(&format!(" WHERE {}", predicate.trim()));
+        }
+        self.execute(&statement, Some(database)).await
+    }
+
+    pub async fn show_continuous_queries(&self, database: &str) -> Result<Vec<ContinuousQuery>> {
+        // One series per database, named after it
+        let series = self.query_series("SHOW CONTINUOUS QUERIES", None).await?;
+
+        Ok(series
+            .into_iter()
+            .filter(|s| s.name.as_deref() == Some(database))
+            .flat_map(|s| {
+                let name_idx = s.columns.iter().position(|c| c == "name").unwrap_or(0);
+                let query_idx = s.columns.iter().position(|c| c == "query").unwrap_or(1);
+                s.values.into_iter().map(move |row| ContinuousQuery {
+                    name: row.get(name_idx).and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                    query: row.get(query_idx).and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                })
+            })
+            .collect())
+    }
+
+    pub async fn drop_continuous_query(&self, database: &str, name: &str) -> Result<()> {
+        let statement = format!(
+            "DROP CONTINUOUS QUERY {} ON {}",
+            quote_ident(name),
+            quote_ident(database)
+        );
+        self.execute(&statement, None).await
+    }
+
+    pub async fn show_users(&self) -> Result<Vec<UserInfo>> {
+        let result = self.query("SHOW USERS", None).await?;
+
+        Ok(result
+            .map(|(_, rows)| {
+                rows.into_iter()
+                    .map(|row| UserInfo {
+                        name: row.first().and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                        admin: row.get(1).and_then(|v| v.as_bool()).unwrap_or(false),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Database privileges of a user; databases without access are left out.
+    pub async fn show_grants(&self, user: &str) -> Result<Vec<(String, Privilege)>> {
+        let query = format!("SHOW GRANTS FOR {}", quote_ident(user));
+        let result = self.query(&query, None).await?;
+
+        Ok(result
+            .map(|(_, rows)| {
+                rows.into_iter()
+                    .filter_map(|row| {
+                        let database = row.first()?.as_str()?.to_string();
+                        let privilege = Privilege::parse(row.get(1)?.as_str()?)?;
+                        Some((database, privilege))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    // Statements carrying a password go through `execute`, so they're sent in a POST body and never end up in a URL
+    pub async fn create_user(&self, name: &str, password: &str, admin: bool) -> Result<()> {
+        let mut statement = format!(
+            "CREATE USER {} WITH PASSWORD {}",
+            quote_ident(name),
+            quote_literal(password)
+        );
+        if admin {
+            statement.push_str(" WITH ALL PRIVILEGES");
+        }
+        self.execute(&statement, None).await
+    }
+
+    pub async fn set_password(&self, name: &str, password: &str) -> Result<()> {
+        let statement = format!(
+            "SET PASSWORD FOR {} = {}",
+            quote_ident(name),
+            quote_literal(password)
+        );
+        self.execute(&statement, None).await
+    }
+
+    pub async fn set_admin(&self, name: &str, admin: bool) -> Result<()> {
+        let statement = if admin {
+            format!("GRANT ALL PRIVILEGES TO {}", quote_ident(name))
+        } else {
+            format!("REVOKE ALL PRIVILEGES FROM {}", quote_ident(name))
+        };
+        self.execute(&statement, None).await
+    }
+
+    /// Changes the user's privilege on the database from `current` to `privilege`; `None` is no access.
+    pub async fn set_privilege(
+        &self,
+        database: &str,
+        user: &str,
+        current: Option<Privilege>,
+        privilege: Option<Privilege>,
+    ) -> Result<()> {
+        let (grant, revoke) = Privilege::changes(current, privilege);
+
+        if let Some(grant) = grant {
+            let statement = format!(
+                "GRANT {} ON {} TO {}",
+                grant.label(),
+                quote_ident(database),
+                quote_ident(user)
+            );
+            self.execute(&statement, None).await?;
+        }
+        if let Some(revoke) = revoke {
+            let statement = format!(
+                "REVOKE {} ON {} FROM {}",
+                revoke.label(),
+                quote_ident(database),
+                quote_ident(user)
+            );
+            // The grant already went through, so say what the user holds now
+            self.execute(&statement, None).await.map_err(|e| {
+                let held = Privilege::from_bits(Privilege::bits(current) | Privilege::bits(privilege));
+                anyhow!("Revoking {} failed, the user keeps {}: {}", revoke.label(), held.map(|p| p.label()).unwrap_or("-"), e)
+            })?;
+        }
+        Ok(())
+    }
+
+    pub async fn drop_user(&self, name: &str) -> Result<()> {
+        self.execute(&format!("DROP USER {}", quote_ident(name)), None).await
+    }
+
+    pub async fn show_queries(&self) -> Result<Vec<RunningQuery>> {
+        let Some((columns, rows)) = self.query("SHOW QUERIES", None).await? else {
+            return Ok(Vec::new());
+        };
+        let column = |name: &str| columns.iter().position(|c| c == name);
+        let (id, query, database, duration, status) = (
+            column("qid"),
+            column("query"),
+            column("database"),
+            column("duration"),
+            column("status"),
+        );
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let duration = row_text(row, duration);
+                RunningQuery {
+                    id: row_u64(row, id),
+                    query: row_text(row, query),
+                    database: row_text(row, database),
+                    duration_ns: parse_go_duration(&duration).unwrap_or(0),
+                    duration,
+                    status: row_text(row, status),
+                }
+            })
+            .collect())
+    }
+
+    /// One series per component (and per database, shard, ... where tagged).
+    pub async fn show_stats(&self) -> Result<Vec<Series>> {
+        self.query_series("SHOW STATS", None).await
+    }
+
+    pub async fn show_diagnostics(&self) -> Result<Vec<Series>> {
+        self.query_series("SHOW DIAGNOSTICS", None).await
+    }
+
+    pub async fn show_shards(&self) -> Result<Vec<Shard>> {
+        // One series per database
+        let series = self.query_series("SHOW SHARDS", None).await?;
+        let mut shards = Vec::new();
+
+        for s in &series {
+            let column = |name: &str| s.columns.iter().position(|c| c == name);
+            let (id, group, owners) = (column("id"), column("shard_group"), column("owners"));
+            for row in &s.values {
+                shards.push(Shard {
+                    id: row_u64(row, id),
+                    shard_group: row_u64(row, group),
+                    owners: row_text(row, owners),
+                });
+            }
+        }
+        Ok(shards)
+    }
+
+    pub async fn show_shard_groups(&self) -> Result<Vec<ShardGroup>> {
+        let Some((columns, rows)) = self.query("SHOW SHARD GROUPS", None).await? else {
+            return Ok(Vec::new());
+        };
+        let column = |name: &str| columns.iter().position(|c| c == name);
+        let (id, db, rp, start, end, expiry) = (
+            column("id"),
+            column("database"),
+            column("retention_policy"),
+            column("start_time"),
+            column("end_time"),
+            column("expiry_time"),
+        );
+
+        Ok(rows
+            .iter()
+            .map(|row| ShardGroup {
+                id: row_u64(row, id),
+                database: row_text(row, db),
+                retention_policy: row_text(row, rp),
+                start_time: row_text(row, start),
+                end_time: row_text(row, end),
+                expiry_time: row_text(row, expiry),
+            })
+            .collect())
+    }
+
+    pub async fn drop_shard(&self, id: u64) -> Result<()> {
+        self.execute(&format!("DROP SHARD {}", id), None).await
+    }
+
+    pub async fn kill_query(&self, id: u64) -> Result<()> {
+        self.execute(&format!("KILL QUERY {}", id), None).await
+    }
+
+    pub async fn drop_retention_policy(&self, database: &str, name: &str) -> Result<()> {
+        let statement = format!(
+            "DROP RETENTION POLICY {} ON {}",
+            quote_ident(name),
+            quote_ident(database)
+        );
+        self.execute(&statement, None).await
+    }
+}
+
+/// Splits a series key like `cpu,host=a,region=eu` into measurement and tag pairs.
+pub fn parse_series_key(key: &str) -> (String, Vec<(String, String)>) {
+    let mut measurement = None;
+    let mut tags = Vec::new();
+    let mut tag_key: Option<String> = None;
+    let mut current = String::new();
+    let mut chars = key.chars();
+
+    // Commas, spaces and equal signs are backslash-escaped in line protocol
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ',' if measurement.is_none() => measurement = Some(std::mem::take(&mut current)),
+            ',' => {
+                if let Some(k) = tag_key.take() {
+                    tags.push((k, std::mem::take(&mut current)));
+                }
+            }
+            '=' if measurement.is_some() && tag_key.is_none() => {
+                tag_key = Some(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+
+    match (measurement, tag_key) {
+        (None, _) => (current, tags),
+        (Some(m), Some(k)) => {
+            tags.push((k, current));
+            (m, tags)
+        }
+        (Some(m), None) => (m, tags),
+    }
+}
+
+fn row_text(row: &[Value], column: Option<usize>) -> String {
+    column
+        .and_then(|i| row.get(i))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn row_u64(row: &[Value], column: Option<usize>) -> u64 {
+    column.and_then(|i| row.get(i)).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+fn exact_keyword(exact: bool) -> &'static str {
+    if exact {
+        "EXACT "
+    } else {
+        ""
+    }
+}
+
+fn sum_counts(series: &[Series]) -> u64 {
+    series
+        .iter()
+        .flat_map(|s| s.values.iter())
+        .filter_map(|row| row.last().and_then(|v| v.as_u64()))
+        .sum()
+}
+
+/// `"host" = 'a' AND "region" = 'eu'` for the tags of a series key. The measurement's other
+/// `tag_keys` are pinned to `''`, so series that carry extra tags don't match too.
+pub fn series_predicate(series_key: &str, tag_keys: &[String]) -> String {
+    let (_, tags) = parse_series_key(series_key);
+    let missing = tag_keys
+        .iter()
+        .filter(|key| !tags.iter().any(|(k, _)| k == *key))
+        .map(|key| format!("{} = ''", quote_ident(key)));
+
+    tags.iter()
+        .map(|(k, v)| format!("{} = {}", quote_ident(k), quote_literal(v)))
+        .chain(missing)
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Single-quotes an InfluxQL string literal.
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Double-quotes an InfluxQL identifier.
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `"db"."rp"."measurement"`, or just the measurement when no RP is selected.
+pub fn qualified_measurement(database: &str, rp: Option<&str>, measurement: &str) -> String {
+    match rp {
+        Some(rp) => format!(
+            "{}.{}.{}",
+            quote_ident(database),
+            quote_ident(rp),
+            quote_ident(measurement)
+        ),
+        None => quote_ident(measurement),
+    }
+}
+
+fn retention_policy_options(rp: &RetentionPolicy) -> String {
+    // SHOW RETENTION POLICIES reports infinite retention as 0s
+    let duration = match rp.duration.trim() {
+        "" | "0" | "0s" | "0h0m0s" => "INF",
+        d => d,
+    };
+    let mut options = format!(" DURATION {} REPLICATION {}", duration, rp.replication.max(1));
+
+    let shard = rp.shard_group_duration.trim();
+    if !shard.is_empty() && shard != "0s" {
+        options.push_str(&format!(" SHARD DURATION {}", shard));
+    }
+    if rp.default {
+        options.push_str(" DEFAULT");
+    }
+    options
+}
+
+/// Nanoseconds in an InfluxQL duration literal such as `90s`, `1h30m` or `7d`.
+pub fn parse_duration(literal: &str) -> Option<i64> {
+    let literal = literal.trim();
+    if literal.is_empty() {
+        return None;
+    }
+
+    let mut total: i64 = 0;
+    let mut rest = literal;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let value: i64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ns" => 1,
+            "u" | "µ" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60 * 1_000_000_000,
+            "h" => 3_600 * 1_000_000_000,
+            "d" => 86_400 * 1_000_000_000,
+            "w" => 604_800 * 1_000_000_000,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total = total.checked_add(value.checked_mul(unit)?)?;
+    }
+    Some(total)
+}
+
+/// Nanoseconds in a Go duration string such as `1.5s`, `250ms` or `168h0m0s`, as reported by `SHOW QUERIES` and `SHOW RETENTION POLICIES`.
+pub fn parse_go_duration(s: &str) -> Option<i64> {
+    let mut total = 0.0;
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let value: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ns" => 1.0,
+            "us" | "µs" | "μs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += value * unit;
+    }
+    Some(total as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_queries_match_reformatted_text() {
+        record_issued("SELECT * FROM \"db\".\"autogen\".\"cpu\"  WHERE \"host\" = 'a'\n LIMIT 10000 OFFSET 0;");
+        assert!(issued_by_app("SELECT * FROM db.autogen.cpu WHERE host = 'a' LIMIT 10000 OFFSET 0"));
+        assert!(!issued_by_app("SELECT * FROM db.autogen.mem"));
+    }
+
+    #[test]
+    fn privilege_changes_grant_before_revoking() {
+        use Privilege::*;
+        let cases = [
+            (None, Some(Read), (Some(Read), None)),
+            (Some(All), None, (None, Some(All))),
+            (Some(Read), Some(Write), (Some(Write), Some(Read))),
+            (Some(All), Some(Read), (None, Some(Write))),
+            (Some(Write), Some(All), (Some(Read), None)),
+            (Some(Read), Some(Read), (None, None)),
+        ];
+        for (from, to, expected) in cases {
+            assert_eq!(Privilege::changes(from, to), expected, "{:?} -> {:?}", from, to);
+        }
+    }
+}
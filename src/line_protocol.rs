@@ -0,0 +1,65 @@
+use serde_json::Value;
+
+fn escape(s: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn escape_measurement(name: &str) -> String {
+    escape(name, &[',', ' '])
+}
+
+/// Tag keys, tag values and field keys share the same escaping rules.
+pub fn escape_key(name: &str) -> String {
+    escape(name, &[',', '=', ' '])
+}
+
+/// Encodes a field value using the type reported by `SHOW FIELD KEYS`, falling back to the JSON type.
+pub fn field_value(value: &Value, field_type: Option<&str>) -> Option<String> {
+    match (value, field_type) {
+        (Value::Null, _) => None,
+        (Value::Number(n), Some("integer")) => Some(format!("{}i", n.as_i64().unwrap_or_else(|| n.as_f64().unwrap_or(0.0) as i64))),
+        (Value::Number(n), Some("unsigned")) => Some(format!("{}u", n.as_u64().unwrap_or(0))),
+        (Value::Number(n), Some("float")) => Some(n.as_f64().map(|f| f.to_string()).unwrap_or_else(|| n.to_string())),
+        (Value::Number(n), _) if n.is_i64() || n.is_u64() => Some(format!("{}i", n)),
+        (Value::Number(n), _) => Some(n.to_string()),
+        (Value::Bool(b), _) => Some(b.to_string()),
+        (Value::String(s), _) => Some(format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))),
+        (other, _) => Some(format!("\"{}\"", other.to_string().replace('\\', "\\\\").replace('"', "\\\""))),
+    }
+}
+
+/// One line, or `None` when the point has no non-null fields (line protocol requires at least one).
+pub fn line(measurement: &str, tags: &[(String, String)], fields: &[(String, String)], timestamp: i64) -> Option<String> {
+    if fields.is_empty() {
+        return None;
+    }
+
+    let mut out = escape_measurement(measurement);
+    for (k, v) in tags {
+        if v.is_empty() {
+            continue;
+        }
+        out.push(',');
+        out.push_str(&escape_key(k));
+        out.push('=');
+        out.push_str(&escape_key(v));
+    }
+
+    out.push(' ');
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(k, v)| format!("{}={}", escape_key(k), v))
+        .collect();
+    out.push_str(&fields.join(","));
+
+    out.push(' ');
+    out.push_str(&timestamp.to_string());
+    Some(out)
+}
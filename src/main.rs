@@ -3,10 +3,45 @@ use eframe::egui;
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
 
+mod cardinality;
+mod chart;
+mod compare;
+mod connections;
+mod continuous_query;
+mod copy;
+mod credentials;
+mod diagnostics;
+mod distribution;
+mod downsample;
+mod explain;
+mod flux;
+mod gaps;
+mod highlight;
 mod influx;
+mod line_protocol;
+mod running_queries;
+mod schema;
+mod server;
+mod session;
+mod shards;
+mod stats;
+mod tail;
 mod ui;
-use influx::InfluxClient;
-use ui::AppState;
+mod users;
+mod workspace;
+use influx::{InfluxClient, SqlFormat};
+use compare::SchemaCompareView;
+use copy::CopyView;
+use diagnostics::DiagnosticsView;
+use running_queries::RunningQueriesView;
+use shards::ShardsView;
+use users::UsersView;
+use session::Session;
+use workspace::WorkspaceTab;
+use ui::{AdminAction, AppState, CentralTab, DataSource, QueryLanguage, RetentionPolicyAction};
+
+// Also names the storage directory used for saved state
+const APP_ID: &str = "vyn InfluxDB Browser";
 
 fn main() -> Result<(), eframe::Error> {
     let runtime = Arc::new(Runtime::new().expect("Failed to create Tokio runtime"));
@@ -19,29 +54,41 @@ fn main() -> Result<(), eframe::Error> {
     };
 
     eframe::run_native(
-        "vyn InfluxDB Browser",
+        APP_ID,
         options,
-        Box::new(|_cc| {
-            Ok(Box::new(InfluxDBApp::new(runtime)) as Box<dyn eframe::App>)
+        Box::new(|cc| {
+            Ok(Box::new(InfluxDBApp::new(cc, runtime)) as Box<dyn eframe::App>)
         }),
     )
 }
 
+// Series keys listed per page in the series panel
+const SERIES_PAGE: usize = 100;
+
 struct InfluxDBApp {
     state: Arc<Mutex<AppState>>,
     runtime: Arc<Runtime>,
 }
 
 impl InfluxDBApp {
-    fn new(runtime: Arc<Runtime>) -> Self {
-        Self {
+    fn new(cc: &eframe::CreationContext<'_>, runtime: Arc<Runtime>) -> Self {
+        let app = Self {
             state: Arc::new(Mutex::new(AppState::default())),
             runtime,
+        };
+        if let Some(session) = cc.storage.and_then(|s| eframe::get_value::<Session>(s, session::SESSION_KEY)) {
+            app.restore_session(&cc.egui_ctx, session);
         }
+        app
     }
 }
 
 impl eframe::App for InfluxDBApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let state = self.state.lock().unwrap();
+        eframe::set_value(storage, session::SESSION_KEY, &Session::capture(&state));
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut state = self.state.lock().unwrap();
 
@@ -52,91 +99,239 @@ impl eframe::App for InfluxDBApp {
                     ui.text_edit_singleline(&mut state.host);
                     ui.label("Proxy:");
                     ui.text_edit_singleline(&mut state.proxy);
-
-                    if ui.button("Connect").clicked() {
-                        let host = state.host.clone();
+                    self.show_login_fields(ui, &mut state.login);
+                    ui.checkbox(&mut state.login.read_only, "Read-only")
+                        .on_hover_text("Disable everything that creates or drops data on this connection");
+
+                    if ui
+                        .button("Connect")
+                        .on_hover_text("Open a connection; the ones already open stay in the sidebar")
+                        .clicked()
+                        && !state.host.trim().is_empty()
+                    {
+                        let host = state.host.trim().to_string();
                         let proxy = if state.proxy.is_empty() {
                             None
                         } else {
                             Some(state.proxy.clone())
                         };
+                        let credentials = state.login.credentials();
+                        state.login.secret.clear();
+                        let id = self.connect(ctx, &mut state, host, proxy, credentials, None);
+                        // Checking the box can make an open connection read-only, never writable again
+                        let read_only = state.login.read_only;
+                        if let Some(connection) = state.connection_by_id(id) {
+                            connection.read_only |= read_only;
+                        }
+                        if state.login.save {
+                            self.save_credentials(&mut state, id);
+                        }
+                    }
+                });
 
-                        let state_clone = Arc::clone(&self.state);
-                        let ctx_clone = ctx.clone();
-
-                        self.runtime.spawn(async move {
-                            {
-                                let mut state = state_clone.lock().unwrap();
-                                state.status = "Connecting...".to_string();
-                                state.is_loading = true;
-                            }
+                ui.separator();
 
-                            ctx_clone.request_repaint();
+                ui.group(|ui| {
+                    ui.label("Query:");
 
-                            let client = InfluxClient::new(host, proxy);
-                            match client.show_databases().await {
-                                Ok(dbs) => {
-                                    let mut state = state_clone.lock().unwrap();
-                                    state.databases = dbs.clone();
-                                    state.status = format!("Connected: {} databases", dbs.len());
-                                    state.is_loading = false;
-                                    state.client = Some(client);
-                                }
-                                Err(e) => {
-                                    let mut state = state_clone.lock().unwrap();
-                                    state.status = format!("Error: {}", e);
-                                    state.is_loading = false;
+                    let supported = QueryLanguage::ALL.map(|l| state.language_supported(l));
+                    let tab_db = state.tab().database.clone();
+                    let mut chosen_db = None;
+                    egui::ComboBox::from_id_salt("query_database")
+                        .selected_text(tab_db.as_deref().unwrap_or("(no database)"))
+                        .show_ui(ui, |ui| {
+                            // The tab's own connection, or the active one for a tab that has none yet
+                            let connection = match state.tab().host() {
+                                Some(host) => state.connections.iter().find(|c| c.host == host),
+                                None => state.connection(),
+                            };
+                            for db in connection.map(|c| c.databases.as_slice()).unwrap_or_default() {
+                                if ui.selectable_label(tab_db.as_ref() == Some(db), db).clicked() {
+                                    chosen_db = Some(db.clone());
                                 }
                             }
-                            ctx_clone.request_repaint();
+                        })
+                        .response
+                        .on_hover_text("Database the tab's queries run against");
+                    if let Some(db) = chosen_db {
+                        let client = state.client();
+                        let tab = state.tab_mut();
+                        if tab.client.is_none() {
+                            tab.client = client;
+                        }
+                        tab.database = Some(db);
+                    }
+
+                    let fallback_client = state.client();
+                    let tab = state.tab_mut();
+                    egui::ComboBox::from_id_salt("query_language")
+                        .selected_text(tab.query_language.label())
+                        .show_ui(ui, |ui| {
+                            for (language, supported) in QueryLanguage::ALL.into_iter().zip(supported) {
+                                ui.add_enabled_ui(supported, |ui| {
+                                    ui.selectable_value(&mut tab.query_language, language, language.label())
+                                        .on_disabled_hover_text("Not supported by this server");
+                                });
+                            }
                         });
+
+                    if tab.query_language == QueryLanguage::Sql {
+                        egui::ComboBox::from_id_salt("sql_format")
+                            .selected_text(tab.sql_format.label())
+                            .show_ui(ui, |ui| {
+                                for format in SqlFormat::ALL {
+                                    ui.selectable_value(&mut tab.sql_format, format, format.label());
+                                }
+                            });
+                    }
+
+                    let language = tab.query_language;
+                    let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                        let mut job = highlight::layout_job(ui, text, language);
+                        job.wrap.max_width = wrap_width;
+                        ui.fonts(|f| f.layout_job(job))
+                    };
+                    // Flux pipelines are naturally multi-line, InfluxQL usually fits on one
+                    let rows = match language {
+                        QueryLanguage::Flux => 4,
+                        QueryLanguage::Sql => 3,
+                        QueryLanguage::InfluxQL => 1,
+                    };
+                    ui.add(
+                        egui::TextEdit::multiline(&mut tab.custom_query)
+                            .id_salt(("query_editor", tab.id))
+                            .code_editor()
+                            .desired_rows(rows)
+                            .desired_width(500.0)
+                            .layouter(&mut layouter),
+                    );
+
+                    let can_execute = supported[QueryLanguage::ALL.iter().position(|l| *l == language).unwrap_or(0)];
+                    if ui.add_enabled(can_execute, egui::Button::new("Execute")).clicked() && !tab.custom_query.is_empty() {
+                        if tab.client.is_none() {
+                            tab.client = fallback_client.clone();
+                        }
+                        let query = tab.custom_query.clone();
+                        self.run_tab_query(ctx, tab, query);
+                    }
+
+                    let mut explain = None;
+                    if ui
+                        .add_enabled(can_execute && language == QueryLanguage::InfluxQL, egui::Button::new("Explain"))
+                        .on_hover_text("EXPLAIN and EXPLAIN ANALYZE the query; ANALYZE runs it")
+                        .on_disabled_hover_text("Only InfluxQL SELECT queries can be explained")
+                        .clicked()
+                        && !tab.custom_query.trim().is_empty()
+                    {
+                        if let Some(client) = tab.client.clone().or(fallback_client) {
+                            explain = Some((client, tab.database.clone(), tab.custom_query.clone()));
+                        }
+                    }
+                    if let Some((client, database, query)) = explain {
+                        self.open_explain(ctx, &mut state, client, database, query);
                     }
                 });
 
                 ui.separator();
 
-                ui.group(|ui| {
-                    ui.label("Query:");
-                    ui.text_edit_singleline(&mut state.custom_query);
-
-                    if ui.button("Execute").clicked() && !state.custom_query.is_empty() {
-                        let query = state.custom_query.clone();
-                        let db = state.selected_db.clone();
-                        let client = state.client.clone();
+                ui.menu_button("Session", |ui| {
+                    ui.checkbox(&mut state.rerun_on_startup, "Re-run queries on startup")
+                        .on_hover_text("Otherwise restored tabs keep their queries but start without results");
+                    ui.separator();
+                    ui.label("Theme:");
+                    egui::widgets::global_theme_preference_buttons(ui);
+                });
 
-                        if let Some(client) = client {
+                ui.menu_button("Tools", |ui| {
+                    if ui.button("Compare schemas...").clicked() {
+                        state.schema_compare = Some(SchemaCompareView::new(state.active_connection, state.selected_db.as_deref()));
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(!state.read_only(), egui::Button::new("Copy data..."))
+                        .on_disabled_hover_text("Disabled in read-only mode")
+                        .clicked()
+                    {
+                        if state.copy_job.is_none() {
+                            state.copy_job = Some(CopyView::new(
+                                &state.host,
+                                state.selected_db.as_deref(),
+                                state.selected_rp.as_deref(),
+                                state.selected_measurement.as_deref(),
+                            ));
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(state.capabilities().kill_query, egui::Button::new("Running queries..."))
+                        .on_disabled_hover_text("Not supported by this server")
+                        .clicked()
+                    {
+                        if let (None, Some(id)) = (&state.running_queries, state.active_connection) {
+                            state.running_queries = Some(RunningQueriesView::new(id));
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(state.capabilities().diagnostics, egui::Button::new("Server diagnostics..."))
+                        .on_disabled_hover_text("Not supported by this server")
+                        .clicked()
+                    {
+                        if let (None, Some(id)) = (&state.diagnostics, state.active_connection) {
+                            state.diagnostics = Some(DiagnosticsView::new(id));
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(state.capabilities().retention_policies, egui::Button::new("Shards..."))
+                        .on_disabled_hover_text("Not supported by this server")
+                        .clicked()
+                    {
+                        if let (None, Some(connection)) = (&state.shards, state.connection()) {
+                            let client = connection.client.clone();
+                            state.shards = Some(ShardsView {
+                                connection: connection.id,
+                                database: state.selected_db.clone(),
+                                loading: true,
+                                ..Default::default()
+                            });
                             let state_clone = Arc::clone(&self.state);
                             let ctx_clone = ctx.clone();
-
                             self.runtime.spawn(async move {
-                                {
-                                    let mut state = state_clone.lock().unwrap();
-                                    state.status = "Executing query...".to_string();
-                                    state.is_loading = true;
-                                }
-
-                                ctx_clone.request_repaint();
-
-                                match client.query(&query, db.as_deref()).await {
-                                    Ok(result) => {
-                                        let mut state = state_clone.lock().unwrap();
-                                        if let Some((cols, rows)) = result {
-                                            state.update_data(cols, rows.clone());
-                                            state.status = format!("Query returned {} rows", rows.len());
-                                        } else {
-                                            state.status = "No results".to_string();
-                                        }
-                                        state.is_loading = false;
-                                    }
-                                    Err(e) => {
-                                        let mut state = state_clone.lock().unwrap();
-                                        state.status = format!("Error: {}", e);
-                                        state.is_loading = false;
-                                    }
-                                }
-                                ctx_clone.request_repaint();
+                                Self::load_shards(state_clone, ctx_clone, client).await;
                             });
                         }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(state.selected_measurement.is_some(), egui::Button::new("Gaps and anomalies..."))
+                        .on_disabled_hover_text("Select a measurement first")
+                        .clicked()
+                    {
+                        if let (Some(db), Some(measurement)) = (state.selected_db.clone(), state.selected_measurement.clone()) {
+                            self.open_gaps(ctx, &mut state, db, measurement);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(state.capabilities().manage_databases, egui::Button::new("Users and privileges..."))
+                        .on_disabled_hover_text("Not supported by this server")
+                        .clicked()
+                    {
+                        if let (None, Some(connection)) = (&state.users, state.connection()) {
+                            let client = connection.client.clone();
+                            state.users = Some(UsersView {
+                                connection: connection.id,
+                                loading: true,
+                                ..Default::default()
+                            });
+                            let state_clone = Arc::clone(&self.state);
+                            let ctx_clone = ctx.clone();
+                            self.runtime.spawn(async move {
+                                Self::load_users(state_clone, ctx_clone, client).await;
+                            });
+                        }
+                        ui.close_menu();
                     }
                 });
             });
@@ -148,233 +343,898 @@ impl eframe::App for InfluxDBApp {
                 if state.is_loading {
                     ui.spinner();
                 }
-            });
-        });
-
-        egui::SidePanel::left("databases_panel")
-            .default_width(200.0)
-            .resizable(false)
-            .show(ctx, |ui| {
-                ui.heading("Databases");
                 ui.separator();
+                let tab = state.tab();
+                ui.label(format!("{}: {}", tab.title, tab.status));
+                if tab.is_loading {
+                    ui.spinner();
+                }
 
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for db in &state.databases.clone() {
-                        let is_selected = state.selected_db.as_ref() == Some(db);
-
-                        if ui.selectable_label(is_selected, db).clicked() {
-                            state.selected_db = Some(db.clone());
-                            let client = state.client.clone();
-                            let db_name = db.clone();
-
-                            if let Some(client) = client {
-                                let state_clone = Arc::clone(&self.state);
-                                let ctx_clone = ctx.clone();
-
-                                self.runtime.spawn(async move {
-                                    {
-                                        let mut state = state_clone.lock().unwrap();
-                                        state.status = "Loading measurements...".to_string();
-                                        state.is_loading = true;
-                                    }
+                if let Some(info) = state.server_info() {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let build = if info.build.is_empty() { "unknown" } else { info.build.as_str() };
+                        ui.label(info.label())
+                            .on_hover_text(format!("Build: {}\n{}", build, info.capabilities().summary()));
+                    });
+                }
+            });
+        });
 
-                                    ctx_clone.request_repaint();
+        self.show_connections_panel(ctx, &mut state);
+
+        self.show_vault_window(ctx, &mut state);
+
+        self.show_retention_policy_dialog(ctx, &mut state);
+        self.show_admin_dialog(ctx, &mut state);
+        self.show_cardinality_window(ctx, &mut state);
+        self.show_compare_window(ctx, &mut state);
+        self.show_copy_window(ctx, &mut state);
+        self.show_downsample_window(ctx, &mut state);
+        self.show_cq_editor(ctx, &mut state);
+        self.show_users_window(ctx, &mut state);
+        self.show_running_queries_window(ctx, &mut state);
+        self.show_diagnostics_window(ctx, &mut state);
+        self.show_shards_window(ctx, &mut state);
+        self.show_gaps_window(ctx, &mut state);
+        self.show_explain_window(ctx, &mut state);
+
+        if state.selected_measurement.is_some() {
+            self.show_series_panel(ctx, &mut state);
+        }
 
-                                    match client.show_measurements(&db_name).await {
-                                        Ok(measurements) => {
-                                            let mut state = state_clone.lock().unwrap();
-                                            state.measurements = measurements.clone();
-                                            state.status = format!("{} measurements", measurements.len());
-                                            state.is_loading = false;
-                                        }
-                                        Err(e) => {
-                                            let mut state = state_clone.lock().unwrap();
-                                            state.status = format!("Error: {}", e);
-                                            state.is_loading = false;
-                                        }
-                                    }
-                                    ctx_clone.request_repaint();
-                                });
-                            }
-                        }
-                    }
-                });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut state.central_tab, CentralTab::Data, "Data");
+                ui.selectable_value(&mut state.central_tab, CentralTab::Schema, "Schema");
             });
+            ui.separator();
 
-        egui::SidePanel::left("measurements_panel")
-            .default_width(250.0)
-            .resizable(false)
-            .show(ctx, |ui| {
-                ui.heading("Measurements");
-                ui.separator();
+            if state.central_tab == CentralTab::Schema {
+                self.show_schema_tab(ui, &mut state);
+                return;
+            }
 
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for measurement in &state.measurements.clone() {
-                        let is_selected = state.selected_measurement.as_ref() == Some(measurement);
-
-                        if ui.selectable_label(is_selected, measurement).clicked() {
-                            state.selected_measurement = Some(measurement.clone());
-                            state.offset = 0;
-                            state.current_data.clear();
-                            state.current_data_strings.clear();
-
-                            let client = state.client.clone();
-                            let db = state.selected_db.clone();
-                            let meas = measurement.clone();
-
-                            if let Some(client) = client {
-                                if let Some(db) = db {
-                                    let state_clone = Arc::clone(&self.state);
-                                    let ctx_clone = ctx.clone();
-
-                                    self.runtime.spawn(async move {
-                                        Self::load_chunk(state_clone, ctx_clone, client, db, meas, 0).await;
-                                    });
-                                }
-                            }
-                        }
-                    }
-                });
-            });
+            self.show_tab_strip(ui, &mut state);
+            ui.separator();
 
-        egui::CentralPanel::default().show(ctx, |ui| {
+            let tab = state.tab_mut();
             ui.horizontal(|ui| {
-                if ui.button("Load More").clicked() {
-                    let client = state.client.clone();
-                    let db = state.selected_db.clone();
-                    let meas = state.selected_measurement.clone();
-                    let offset = state.offset;
+                if ui.add_enabled(!tab.tail.enabled, egui::Button::new("Load More")).clicked() {
+                    let offset = tab.offset;
 
-                    if let (Some(client), Some(db), Some(meas)) = (client, db, meas) {
+                    if let (Some(client), Some(source)) = (tab.client.clone(), tab.source.clone()) {
                         let state_clone = Arc::clone(&self.state);
                         let ctx_clone = ctx.clone();
+                        let tab_id = tab.id;
 
                         self.runtime.spawn(async move {
-                            Self::load_chunk(state_clone, ctx_clone, client, db, meas, offset).await;
+                            Self::load_chunk(state_clone, ctx_clone, tab_id, client, source, offset).await;
                         });
                     }
                 }
 
                 if ui.button("Export Visible").clicked() {
-                    Self::export_visible(&state);
+                    Self::export_visible(tab);
                 }
 
                 if ui.button("Export ALL").clicked() {
-                    let client = state.client.clone();
-                    let db = state.selected_db.clone();
-                    let meas = state.selected_measurement.clone();
-
-                    if let (Some(client), Some(db), Some(meas)) = (client, db, meas) {
+                    if let (Some(client), Some(source)) = (tab.client.clone(), tab.source.clone()) {
                         let state_clone = Arc::clone(&self.state);
                         let ctx_clone = ctx.clone();
 
                         self.runtime.spawn(async move {
-                            Self::export_all(state_clone, ctx_clone, client, db, meas).await;
+                            Self::export_all(state_clone, ctx_clone, client, source).await;
                         });
                     }
                 }
 
-                ui.label(format!("Rows: {}", state.current_data.len()));
+                ui.label(format!("Rows: {}", tab.current_data.len()));
+
+                if !tab.flux_tables.is_empty() {
+                    ui.separator();
+                    ui.label(format!("Tables: {}", tab.flux_tables.len()));
+                    ui.checkbox(&mut tab.flux_flatten, "Flatten");
+                }
+
+                ui.separator();
+                ui.checkbox(&mut tab.stats.visible, "Statistics")
+                    .on_hover_text("Per-column aggregates of the loaded rows");
+                ui.checkbox(&mut tab.chart.visible, "Chart");
+                ui.checkbox(&mut tab.distribution.visible, "Distribution")
+                    .on_hover_text("Histogram and box plot of a numeric column");
+                if tab.chart.visible {
+                    let numeric = chart::numeric_columns(&tab.current_columns, &tab.current_data);
+                    ui.menu_button("Fields", |ui| {
+                        for column in &numeric {
+                            let mut shown = tab.chart.columns.contains(column);
+                            if ui.checkbox(&mut shown, column).changed() {
+                                if shown {
+                                    tab.chart.columns.push(column.clone());
+                                } else {
+                                    tab.chart.columns.retain(|c| c != column);
+                                }
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+                self.show_tail_controls(ui, tab);
             });
 
             ui.separator();
 
-            // Virtualized table for performance with large datasets
-            if !state.current_columns.is_empty() {
-                use egui_extras::{TableBuilder, Column};
-
-                let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
-                let num_columns = state.current_columns.len();
-
-                TableBuilder::new(ui)
-                    .striped(true)
-                    .resizable(true)
-                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                    .columns(Column::auto().at_least(120.0).resizable(true), num_columns)
-                    .header(20.0, |mut header| {
-                        for col in &state.current_columns {
-                            header.col(|ui| {
-                                ui.strong(col);
+            if tab.chart.visible && !tab.current_columns.is_empty() {
+                let mut selected: Vec<String> = tab
+                    .chart
+                    .columns
+                    .iter()
+                    .filter(|c| tab.current_columns.contains(c))
+                    .cloned()
+                    .collect();
+                if selected.is_empty() {
+                    selected = chart::numeric_columns(&tab.current_columns, &tab.current_data)
+                        .into_iter()
+                        .take(1)
+                        .collect();
+                }
+                let lines = chart::lines_from_rows(&tab.current_columns, &tab.current_data, &selected);
+                chart::line_chart(ui, &lines, 200.0);
+                ui.separator();
+            }
+
+            if tab.distribution.visible && !tab.current_columns.is_empty() {
+                self.show_distribution(ctx, ui, tab);
+                ui.separator();
+            }
+
+            if tab.stats.visible && !tab.current_columns.is_empty() {
+                tab.stats.update(&tab.current_columns, &tab.current_data);
+                stats::stats_table(ui, tab.id, &tab.stats);
+                ui.separator();
+            }
+
+            if !tab.flux_tables.is_empty() && !tab.flux_flatten {
+                Self::show_flux_tables(ui, tab);
+            } else if !tab.current_columns.is_empty() {
+                let tailing = tab.tail.enabled;
+                let highlight = tab.tail.highlight_from(tab.current_data_strings.len());
+                let scroll_to_end = std::mem::take(&mut tab.tail.scroll_to_end);
+                let at_bottom = Self::show_table(
+                    ui,
+                    ("data_table", tab.id),
+                    &tab.current_columns,
+                    &tab.current_data_strings,
+                    highlight,
+                    tailing,
+                    scroll_to_end,
+                );
+
+                // Scrolling up to read pauses the tail; scrolling back down resumes it
+                if tailing && !scroll_to_end {
+                    tab.tail.paused = !at_bottom;
+                }
+            }
+        });
+
+        self.poll_tail(ctx, &mut state);
+    }
+}
+
+impl InfluxDBApp {
+    // Virtualized table for performance with large datasets; returns whether it is scrolled to the bottom
+    fn show_table(
+        ui: &mut egui::Ui,
+        id_salt: impl std::hash::Hash,
+        columns: &[String],
+        rows: &[Vec<String>],
+        highlight_from: Option<usize>,
+        stick_to_bottom: bool,
+        scroll_to_end: bool,
+    ) -> bool {
+        use egui_extras::{TableBuilder, Column};
+
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+
+        let mut table = TableBuilder::new(ui)
+            .id_salt(id_salt)
+            .striped(true)
+            .resizable(true)
+            .stick_to_bottom(stick_to_bottom);
+        if scroll_to_end && !rows.is_empty() {
+            table = table.animate_scrolling(false).scroll_to_row(rows.len() - 1, Some(egui::Align::BOTTOM));
+        }
+
+        let output = table
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .columns(Column::auto().at_least(120.0).resizable(true), columns.len())
+            .header(20.0, |mut header| {
+                for col in columns {
+                    header.col(|ui| {
+                        ui.strong(col);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(text_height, rows.len(), |mut row| {
+                    let row_index = row.index();
+                    row.set_selected(highlight_from.is_some_and(|from| row_index >= from));
+                    if let Some(data_row) = rows.get(row_index) {
+                        for value_str in data_row {
+                            row.col(|ui| {
+                                ui.label(value_str);
                             });
                         }
-                    })
-                    .body(|body| {
-                        body.rows(text_height, state.current_data_strings.len(), |mut row| {
-                            let row_index = row.index();
-                            if let Some(data_row) = state.current_data_strings.get(row_index) {
-                                for value_str in data_row {
-                                    row.col(|ui| {
-                                        ui.label(value_str);
+                    }
+                });
+            });
+
+        output.state.offset.y + output.inner_rect.height() >= output.content_size.y - text_height
+    }
+
+    fn show_flux_tables(ui: &mut egui::Ui, tab: &WorkspaceTab) {
+        use egui_extras::{TableBuilder, Column};
+
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (i, table) in tab.flux_tables.iter().enumerate() {
+                let title = format!("{} ({} rows)", table.group_key_label(), table.rows.len());
+
+                egui::CollapsingHeader::new(title)
+                    .id_salt(("flux_table", tab.id, i))
+                    .default_open(i < 10)
+                    .show(ui, |ui| {
+                        TableBuilder::new(ui)
+                            .id_salt(("flux_grid", tab.id, i))
+                            .striped(true)
+                            .resizable(true)
+                            .vscroll(true)
+                            .max_scroll_height(300.0)
+                            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                            .columns(Column::auto().at_least(120.0).resizable(true), table.columns.len())
+                            .header(20.0, |mut header| {
+                                for col in &table.columns {
+                                    header.col(|ui| {
+                                        ui.strong(col);
                                     });
                                 }
-                            }
-                        });
+                            })
+                            .body(|body| {
+                                body.rows(text_height, table.rows.len(), |mut row| {
+                                    if let Some(values) = table.rows.get(row.index()) {
+                                        for value in values {
+                                            row.col(|ui| {
+                                                ui.label(ui::value_to_string(value));
+                                            });
+                                        }
+                                    }
+                                });
+                            });
                     });
             }
         });
     }
-}
 
-impl InfluxDBApp {
-    async fn load_chunk(
+    /// Runs `query` in the tab's language against its connection and database; the result replaces the tab's table.
+    fn run_tab_query(&self, ctx: &egui::Context, tab: &mut WorkspaceTab, query: String) {
+        let Some(client) = tab.client.clone() else {
+            return;
+        };
+        // The query result replaces the tailed table
+        tab.tail.stop();
+        tab.source = None;
+        tab.executed_query = Some(query.clone());
+
+        let db = tab.database.clone();
+        let tab_id = tab.id;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        match tab.query_language {
+            QueryLanguage::InfluxQL => {
+                self.runtime.spawn(async move {
+                    Self::execute_influxql(state_clone, ctx_clone, tab_id, client, query, db).await;
+                });
+            }
+            QueryLanguage::Flux => {
+                self.runtime.spawn(async move {
+                    Self::execute_flux(state_clone, ctx_clone, tab_id, client, query).await;
+                });
+            }
+            QueryLanguage::Sql => {
+                let format = tab.sql_format;
+                if let Some(db) = db {
+                    self.runtime.spawn(async move {
+                        Self::execute_sql(state_clone, ctx_clone, tab_id, client, query, db, format).await;
+                    });
+                } else {
+                    tab.status = "Select a database to run SQL".to_string();
+                }
+            }
+        }
+    }
+
+    async fn execute_influxql(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        tab_id: u64,
+        client: InfluxClient,
+        query: String,
+        db: Option<String>,
+    ) {
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            tab.status = "Executing query...".to_string();
+            tab.is_loading = true;
+        }
+
+        ctx.request_repaint();
+
+        influx::record_issued(&query);
+        let result = client.query(&query, db.as_deref()).await;
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            match result {
+                Ok(Some((cols, rows))) => {
+                    tab.status = format!("Query returned {} rows", rows.len());
+                    tab.update_data(cols, rows);
+                }
+                Ok(None) => tab.status = "No results".to_string(),
+                Err(e) => tab.status = format!("Error: {}", e),
+            }
+            tab.is_loading = false;
+        }
+        ctx.request_repaint();
+    }
+
+    async fn execute_flux(
         state: Arc<Mutex<AppState>>,
         ctx: egui::Context,
+        tab_id: u64,
         client: InfluxClient,
+        query: String,
+    ) {
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            tab.status = "Executing Flux query...".to_string();
+            tab.is_loading = true;
+        }
+
+        ctx.request_repaint();
+
+        let result = client.query_flux(&query).await;
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            match result {
+                Ok(tables) if tables.is_empty() => tab.status = "No results".to_string(),
+                Ok(tables) => {
+                    let rows: usize = tables.iter().map(|t| t.rows.len()).sum();
+                    tab.status = format!("Flux query returned {} tables, {} rows", tables.len(), rows);
+                    tab.set_flux_tables(tables);
+                }
+                Err(e) => tab.status = format!("Error: {}", e),
+            }
+            tab.is_loading = false;
+        }
+        ctx.request_repaint();
+    }
+
+    async fn execute_sql(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        tab_id: u64,
+        client: InfluxClient,
+        query: String,
         db: String,
-        measurement: String,
-        offset: usize,
+        format: SqlFormat,
     ) {
-        {
-            let mut state_guard = state.lock().unwrap();
-            state_guard.status = format!("Loading rows from offset {}...", offset);
-            state_guard.is_loading = true;
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            tab.status = "Executing SQL query...".to_string();
+            tab.is_loading = true;
         }
 
         ctx.request_repaint();
 
-        const CHUNK_SIZE: usize = 10000;
-        let query = format!(
-            r#"SELECT * FROM "{}" LIMIT {} OFFSET {}"#,
-            measurement, CHUNK_SIZE, offset
-        );
+        let result = client.query_sql(&query, &db, format).await;
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            match result {
+                Ok(Some((cols, rows))) => {
+                    tab.status = format!("Query returned {} rows", rows.len());
+                    tab.update_data(cols, rows);
+                }
+                Ok(None) => tab.status = "No results".to_string(),
+                Err(e) => tab.status = format!("Error: {}", e),
+            }
+            tab.is_loading = false;
+        }
+        ctx.request_repaint();
+    }
 
-        match client.query(&query, Some(&db)).await {
-            Ok(Some((cols, rows))) => {
-                let mut state = state.lock().unwrap();
+    fn show_retention_policy_dialog(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(dialog) = &mut state.rp_dialog else {
+            return;
+        };
+
+        let title = match dialog.action {
+            RetentionPolicyAction::Create => "Create retention policy",
+            RetentionPolicyAction::Alter => "Alter retention policy",
+            RetentionPolicyAction::Drop => "Drop retention policy",
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
 
-                if offset == 0 {
-                    state.update_data(cols, rows.clone());
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if dialog.action == RetentionPolicyAction::Drop {
+                    ui.label(format!(
+                        "Drop retention policy \"{}\" on \"{}\"? All data in it will be deleted.",
+                        dialog.policy.name, dialog.database
+                    ));
+                    ui.label("Type the retention policy name to confirm:");
+                    ui.text_edit_singleline(&mut dialog.confirm);
                 } else {
-                    state.extend_data(rows.clone());
+                    egui::Grid::new("rp_form").num_columns(2).show(ui, |ui| {
+                        ui.label("Database:");
+                        ui.label(&dialog.database);
+                        ui.end_row();
+
+                        ui.label("Name:");
+                        ui.add_enabled(
+                            dialog.action == RetentionPolicyAction::Create,
+                            egui::TextEdit::singleline(&mut dialog.policy.name),
+                        );
+                        ui.end_row();
+
+                        ui.label("Duration:");
+                        ui.text_edit_singleline(&mut dialog.policy.duration)
+                            .on_hover_text("e.g. 30d, 52w or INF");
+                        ui.end_row();
+
+                        ui.label("Shard duration:");
+                        ui.text_edit_singleline(&mut dialog.policy.shard_group_duration)
+                            .on_hover_text("Leave empty for the server default");
+                        ui.end_row();
+
+                        ui.label("Replication:");
+                        ui.add(egui::DragValue::new(&mut dialog.policy.replication).range(1..=16));
+                        ui.end_row();
+
+                        ui.label("Default:");
+                        ui.checkbox(&mut dialog.policy.default, "");
+                        ui.end_row();
+                    });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let label = match dialog.action {
+                        RetentionPolicyAction::Create => "Create",
+                        RetentionPolicyAction::Alter => "Alter",
+                        RetentionPolicyAction::Drop => "Drop",
+                    };
+                    let valid = match dialog.action {
+                        RetentionPolicyAction::Drop => dialog.confirm == dialog.policy.name,
+                        _ => !dialog.policy.name.trim().is_empty(),
+                    };
+                    if ui.add_enabled(valid, egui::Button::new(label)).clicked() {
+                        submit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if !open || cancel {
+            state.rp_dialog = None;
+            return;
+        }
+
+        if !submit {
+            return;
+        }
+
+        let Some(dialog) = state.rp_dialog.take() else {
+            return;
+        };
+        let connection = dialog.connection;
+        let Some(client) = state.connection_of(connection).filter(|c| !c.read_only).map(|c| c.client.clone()) else {
+            state.status = "Error: the connection is gone or read-only".to_string();
+            return;
+        };
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            let db = dialog.database.clone();
+            let result = match dialog.action {
+                RetentionPolicyAction::Create => client.create_retention_policy(&db, &dialog.policy).await,
+                RetentionPolicyAction::Alter => client.alter_retention_policy(&db, &dialog.policy).await,
+                RetentionPolicyAction::Drop => client.drop_retention_policy(&db, &dialog.policy.name).await,
+            };
+
+            {
+                let mut state = state_clone.lock().unwrap();
+                let done = match dialog.action {
+                    RetentionPolicyAction::Create => "created",
+                    RetentionPolicyAction::Alter => "altered",
+                    RetentionPolicyAction::Drop => "dropped",
+                };
+                state.status = match &result {
+                    Ok(()) => format!("Retention policy \"{}\" {}", dialog.policy.name, done),
+                    Err(e) => format!("Error: {}", e),
+                };
+            }
+
+            Self::load_retention_policies(state_clone, ctx_clone, connection, client, db).await;
+        });
+    }
+
+    fn show_admin_dialog(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(dialog) = &mut state.admin_dialog else {
+            return;
+        };
+
+        let title = match &dialog.action {
+            AdminAction::CreateDatabase { .. } => "Create database",
+            AdminAction::DropDatabase { .. } => "Drop database",
+            AdminAction::DropMeasurement { .. } => "Drop measurement",
+            AdminAction::DropSeries { .. } => "Drop series",
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
+
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                match &mut dialog.action {
+                    AdminAction::CreateDatabase { name, with_rp, rp } => {
+                        egui::Grid::new("create_db_form").num_columns(2).show(ui, |ui| {
+                            ui.label("Name:");
+                            ui.text_edit_singleline(name);
+                            ui.end_row();
+
+                            ui.label("Retention policy:");
+                            ui.checkbox(with_rp, "Set options");
+                            ui.end_row();
+
+                            if *with_rp {
+                                ui.label("RP name:");
+                                ui.text_edit_singleline(&mut rp.name)
+                                    .on_hover_text("Leave empty for autogen");
+                                ui.end_row();
+
+                                ui.label("Duration:");
+                                ui.text_edit_singleline(&mut rp.duration);
+                                ui.end_row();
+
+                                ui.label("Shard duration:");
+                                ui.text_edit_singleline(&mut rp.shard_group_duration);
+                                ui.end_row();
+
+                                ui.label("Replication:");
+                                ui.add(egui::DragValue::new(&mut rp.replication).range(1..=16));
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    AdminAction::DropDatabase { name } => {
+                        ui.label(format!("Drop database \"{}\" and all of its data?", name));
+                    }
+                    AdminAction::DropMeasurement { database, measurement } => {
+                        ui.label(format!(
+                            "Drop measurement \"{}\" from \"{}\"? All of its series and points will be deleted.",
+                            measurement, database
+                        ));
+                    }
+                    AdminAction::DropSeries { database, measurement, predicate } => {
+                        ui.label(format!("Drop series of \"{}\" in \"{}\"", measurement, database));
+                        ui.horizontal(|ui| {
+                            ui.label("WHERE");
+                            ui.text_edit_singleline(predicate)
+                                .on_hover_text("Tag predicate, e.g. \"host\" = 'server01'. Empty drops all series.");
+                        });
+                    }
                 }
 
-                state.offset += rows.len();
-                state.status = format!("Loaded {} rows", state.current_data.len());
+                let confirmed = match dialog.action.confirmation_name() {
+                    Some(expected) => {
+                        ui.label(format!("Type \"{}\" to confirm:", expected));
+                        ui.text_edit_singleline(&mut dialog.confirm);
+                        dialog.confirm == expected
+                    }
+                    None => match &dialog.action {
+                        AdminAction::CreateDatabase { name, .. } => !name.trim().is_empty(),
+                        _ => true,
+                    },
+                };
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let label = match &dialog.action {
+                        AdminAction::CreateDatabase { .. } => "Create",
+                        _ => "Drop",
+                    };
+                    if ui.add_enabled(confirmed, egui::Button::new(label)).clicked() {
+                        submit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if !open || cancel {
+            state.admin_dialog = None;
+            return;
+        }
+
+        if !submit {
+            return;
+        }
+
+        let Some(dialog) = state.admin_dialog.take() else {
+            return;
+        };
+        let connection = dialog.connection;
+        let administrable = state
+            .connection_of(connection)
+            .filter(|c| !c.read_only && c.capabilities().manage_databases);
+        let Some(client) = administrable.map(|c| c.client.clone()) else {
+            state.status = "Error: the connection is gone or read-only".to_string();
+            return;
+        };
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            {
+                let mut state = state_clone.lock().unwrap();
+                state.status = "Executing...".to_string();
+                state.is_loading = true;
+            }
+
+            ctx_clone.request_repaint();
+
+            let (result, done, refresh_db) = match &dialog.action {
+                AdminAction::CreateDatabase { name, with_rp, rp } => (
+                    client.create_database(name, with_rp.then_some(rp)).await,
+                    format!("Database \"{}\" created", name),
+                    None,
+                ),
+                AdminAction::DropDatabase { name } => (
+                    client.drop_database(name).await,
+                    format!("Database \"{}\" dropped", name),
+                    None,
+                ),
+                AdminAction::DropMeasurement { database, measurement } => (
+                    client.drop_measurement(database, measurement).await,
+                    format!("Measurement \"{}\" dropped", measurement),
+                    Some(database.clone()),
+                ),
+                AdminAction::DropSeries { database, measurement, predicate } => (
+                    client.drop_series(database, measurement, predicate).await,
+                    format!("Series dropped from \"{}\"", measurement),
+                    Some(database.clone()),
+                ),
+            };
+
+            if let Err(e) = result {
+                let mut state = state_clone.lock().unwrap();
+                state.status = format!("Error: {}", e);
                 state.is_loading = false;
+                ctx_clone.request_repaint();
+                return;
             }
-            Ok(None) => {
-                let mut state = state.lock().unwrap();
-                state.status = "No more data".to_string();
+
+            match refresh_db {
+                Some(db) => Self::refresh_measurements(Arc::clone(&state_clone), connection, &client, &db).await,
+                None => Self::refresh_databases(Arc::clone(&state_clone), connection, &client).await,
+            }
+
+            {
+                let mut state = state_clone.lock().unwrap();
+                state.status = done;
                 state.is_loading = false;
             }
+            ctx_clone.request_repaint();
+        });
+    }
+
+    /// Loads the first rows of a measurement of the selected database into the active tab, or a new one.
+    fn open_measurement(&self, ctx: &egui::Context, state: &mut AppState, measurement: String, new_tab: bool) {
+        state.selected_measurement = Some(measurement.clone());
+        state.selected_series = None;
+        state.series_offset = 0;
+        state.series_keys.clear();
+        state.series_tag_keys.clear();
+        if new_tab {
+            state.open_tab();
+        } else {
+            state.attach_active_tab();
+        }
+
+        let source = state.data_source();
+        let tab = state.tab_mut();
+        tab.reset_data(source.clone());
+        if !tab.renamed {
+            tab.title = measurement;
+        }
+        let tab_id = tab.id;
+
+        if let (Some(client), Some(source)) = (state.client(), source) {
+            let state_clone = Arc::clone(&self.state);
+            let ctx_clone = ctx.clone();
+            let filter = state.series_filter.clone();
+
+            self.runtime.spawn(async move {
+                Self::load_series(Arc::clone(&state_clone), ctx_clone.clone(), client.clone(), source.clone(), filter, 0).await;
+                Self::load_chunk(state_clone, ctx_clone, tab_id, client, source, 0).await;
+            });
+        }
+    }
+
+    fn show_series_panel(&self, ctx: &egui::Context, state: &mut AppState) {
+        let mut reload_series = None;
+        let mut load_points = false;
+
+        egui::SidePanel::left("series_panel")
+            .default_width(250.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("Series");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut state.series_filter)
+                            .hint_text("\"host\" = 'server01'")
+                            .desired_width(160.0),
+                    );
+                    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("Filter").clicked() || submitted {
+                        reload_series = Some(0);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(state.series_offset > 0, egui::Button::new("<")).clicked() {
+                        reload_series = Some(state.series_offset.saturating_sub(SERIES_PAGE));
+                    }
+                    ui.label(format!(
+                        "{}-{}",
+                        state.series_offset + 1,
+                        state.series_offset + state.series_keys.len()
+                    ));
+                    if ui.add_enabled(state.series_keys.len() == SERIES_PAGE, egui::Button::new(">")).clicked() {
+                        reload_series = Some(state.series_offset + SERIES_PAGE);
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if ui.selectable_label(state.selected_series.is_none(), "(all series)").clicked() {
+                        state.selected_series = None;
+                        load_points = true;
+                    }
+
+                    for key in &state.series_keys.clone() {
+                        let is_selected = state.selected_series.as_ref() == Some(key);
+                        if ui.selectable_label(is_selected, key).clicked() {
+                            state.selected_series = Some(key.clone());
+                            load_points = true;
+                        }
+                    }
+                });
+            });
+
+        let (Some(client), Some(source)) = (state.client(), state.data_source()) else {
+            return;
+        };
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        if let Some(offset) = reload_series {
+            let filter = state.series_filter.clone();
+            self.runtime.spawn(async move {
+                Self::load_series(state_clone, ctx_clone, client, source, filter, offset).await;
+            });
+        } else if load_points {
+            state.attach_active_tab();
+            let tab = state.tab_mut();
+            tab.reset_data(Some(source.clone()));
+            let tab_id = tab.id;
+            self.runtime.spawn(async move {
+                Self::load_chunk(state_clone, ctx_clone, tab_id, client, source, 0).await;
+            });
+        }
+    }
+
+    async fn load_series(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        source: DataSource,
+        filter: String,
+        offset: usize,
+    ) {
+        let series = client.show_series(&source.db, &source.measurement, Some(&filter), SERIES_PAGE, offset).await;
+        let tag_keys = client.show_tag_keys(&source.db, &source.measurement).await;
+        match series.and_then(|keys| Ok((keys, tag_keys?))) {
+            Ok((keys, tag_keys)) => {
+                let mut state = state.lock().unwrap();
+                if state.selected_measurement.as_deref() == Some(source.measurement.as_str()) {
+                    state.series_keys = keys;
+                    state.series_tag_keys = tag_keys;
+                    state.series_offset = offset;
+                }
+            }
             Err(e) => {
                 let mut state = state.lock().unwrap();
                 state.status = format!("Error: {}", e);
-                state.is_loading = false;
             }
         }
+        ctx.request_repaint();
+    }
+
+    async fn load_chunk(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        tab_id: u64,
+        client: InfluxClient,
+        source: DataSource,
+        offset: usize,
+    ) {
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            tab.status = format!("Loading rows from offset {}...", offset);
+            tab.is_loading = true;
+        }
+
+        ctx.request_repaint();
+
+        const CHUNK_SIZE: usize = 10000;
+        let query = source.select(CHUNK_SIZE, offset);
+        influx::record_issued(&query);
+        let result = client.query(&query, Some(&source.db)).await;
+
+        if let Some(tab) = state.lock().unwrap().tab_by_id(tab_id) {
+            match result {
+                Ok(Some((cols, rows))) => {
+                    tab.offset += rows.len();
+                    if offset == 0 {
+                        tab.update_data(cols, rows);
+                    } else {
+                        tab.extend_data(rows);
+                    }
+                    tab.status = format!("Loaded {} rows", tab.current_data.len());
+                }
+                Ok(None) => tab.status = "No more data".to_string(),
+                Err(e) => tab.status = format!("Error: {}", e),
+            }
+            tab.is_loading = false;
+        }
 
         ctx.request_repaint();
     }
 
-    fn export_visible(state: &AppState) {
-        if state.current_data.is_empty() {
+    fn export_visible(tab: &WorkspaceTab) {
+        if tab.current_data.is_empty() {
             return;
         }
 
         let filename = format!(
             "{}_{}.csv",
-            state.selected_measurement.as_ref().unwrap_or(&"export".to_string()),
+            tab.source.as_ref().map(|s| s.label()).unwrap_or("export"),
             chrono::Local::now().format("%Y%m%d_%H%M%S")
         );
 
@@ -383,9 +1243,9 @@ impl InfluxDBApp {
             .save_file()
         {
             let mut writer = csv::Writer::from_path(path).unwrap();
-            writer.write_record(&state.current_columns).unwrap();
+            writer.write_record(&tab.current_columns).unwrap();
 
-            for row in &state.current_data {
+            for row in &tab.current_data {
                 let row_strings: Vec<String> = row.iter().map(|v| v.to_string()).collect();
                 writer.write_record(&row_strings).unwrap();
             }
@@ -398,12 +1258,11 @@ impl InfluxDBApp {
         state: Arc<Mutex<AppState>>,
         ctx: egui::Context,
         client: InfluxClient,
-        db: String,
-        measurement: String,
+        source: DataSource,
     ) {
         let filename = format!(
             "{}_full_{}.csv",
-            measurement,
+            source.label(),
             chrono::Local::now().format("%Y%m%d_%H%M%S")
         );
 
@@ -431,12 +1290,9 @@ impl InfluxDBApp {
         let mut first = true;
 
         loop {
-            let query = format!(
-                r#"SELECT * FROM "{}" LIMIT {} OFFSET {}"#,
-                measurement, CHUNK_SIZE, offset
-            );
+            let query = source.select(CHUNK_SIZE, offset);
 
-            match client.query(&query, Some(&db)).await {
+            match client.query(&query, Some(&source.db)).await {
                 Ok(Some((cols, rows))) => {
                     if first {
                         writer.write_record(&cols).unwrap();
@@ -0,0 +1,213 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::influx::{self, InfluxClient, RunningQuery};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+// Longer query texts are cut in the table and shown whole on hover
+const QUERY_PREVIEW: usize = 120;
+
+pub struct RunningQueriesView {
+    /// Connection the window was opened on
+    pub connection: u64,
+    pub queries: Vec<RunningQuery>,
+    pub auto_refresh: bool,
+    pub interval_secs: u64,
+    pub last_refresh: Option<Instant>,
+    pub longest_first: bool,
+    pub confirm_kill: Option<RunningQuery>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl RunningQueriesView {
+    pub fn new(connection: u64) -> Self {
+        Self {
+            connection,
+            queries: Vec::new(),
+            auto_refresh: true,
+            interval_secs: 2,
+            last_refresh: None,
+            longest_first: true,
+            confirm_kill: None,
+            loading: false,
+            error: None,
+        }
+    }
+
+    fn sort(&mut self) {
+        self.queries.sort_by_key(|q| q.duration_ns);
+        if self.longest_first {
+            self.queries.reverse();
+        }
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_running_queries_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.running_queries.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(connection) = state.connection_of(id) else {
+            state.running_queries = None;
+            return;
+        };
+        let client = connection.client.clone();
+        let can_kill = !connection.read_only && connection.capabilities().kill_query;
+        let Some(view) = &mut state.running_queries else {
+            return;
+        };
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut kill = None;
+
+        egui::Window::new(format!("Running queries - {}", client.host()))
+            .id(egui::Id::new("running_queries_window"))
+            .open(&mut open)
+            .default_size([800.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                    ui.checkbox(&mut view.auto_refresh, "Auto refresh every");
+                    ui.add(egui::DragValue::new(&mut view.interval_secs).range(1..=60).suffix(" s"));
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                if let Some(query) = &view.confirm_kill {
+                    let mut done = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Kill query {} ({})?", query.id, query.duration));
+                        if ui.button("Kill").clicked() {
+                            kill = Some(query.id);
+                            done = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            done = true;
+                        }
+                    });
+                    if done {
+                        view.confirm_kill = None;
+                    }
+                }
+
+                ui.separator();
+
+                let own = egui::Color32::from_rgb(80, 160, 220);
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("running_queries").striped(true).show(ui, |ui| {
+                        ui.strong("ID");
+                        let arrow = if view.longest_first { " v" } else { " ^" };
+                        if ui.button(format!("Duration{}", arrow)).clicked() {
+                            view.longest_first = !view.longest_first;
+                            view.sort();
+                        }
+                        ui.strong("Database");
+                        ui.strong("Status");
+                        ui.strong("Query");
+                        ui.label("");
+                        ui.end_row();
+
+                        for query in &view.queries {
+                            let mine = influx::issued_by_app(&query.query);
+                            let text = |s: &str| {
+                                let text = egui::RichText::new(s);
+                                if mine {
+                                    text.color(own)
+                                } else {
+                                    text
+                                }
+                            };
+
+                            ui.label(text(&query.id.to_string()));
+                            ui.label(text(&query.duration));
+                            ui.label(text(&query.database));
+                            ui.label(text(&query.status));
+
+                            let preview: String = query.query.chars().take(QUERY_PREVIEW).collect();
+                            let response = ui.label(text(&preview).monospace());
+                            if mine {
+                                response.on_hover_text(format!("Issued by this app\n\n{}", query.query));
+                            } else if preview.len() < query.query.len() {
+                                response.on_hover_text(&query.query);
+                            }
+
+                            if ui
+                                .add_enabled(can_kill, egui::Button::new("Kill"))
+                                .on_disabled_hover_text("Not available in read-only mode or on this server")
+                                .clicked()
+                            {
+                                view.confirm_kill = Some(query.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        if !open {
+            state.running_queries = None;
+            return;
+        }
+
+        if view.auto_refresh && !view.loading {
+            let interval = Duration::from_secs(view.interval_secs);
+            match view.last_refresh {
+                Some(last) if last.elapsed() < interval => ctx.request_repaint_after(interval - last.elapsed()),
+                _ => refresh = true,
+            }
+        }
+
+        if kill.is_none() && !refresh {
+            return;
+        }
+
+        view.loading = true;
+        view.last_refresh = Some(Instant::now());
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            if let Some(id) = kill {
+                let result = client.kill_query(id).await;
+                let mut state = state_clone.lock().unwrap();
+                state.status = match result {
+                    Ok(()) => format!("Killed query {}", id),
+                    Err(e) => format!("Error: {}", e),
+                };
+            }
+            Self::load_running_queries(state_clone, ctx_clone, client).await;
+        });
+    }
+
+    async fn load_running_queries(state: Arc<Mutex<AppState>>, ctx: egui::Context, client: InfluxClient) {
+        let result = client.show_queries().await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.running_queries {
+                view.loading = false;
+                view.last_refresh = Some(Instant::now());
+                match result {
+                    Ok(queries) => {
+                        view.queries = queries;
+                        view.sort();
+                        view.error = None;
+                    }
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
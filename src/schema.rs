@@ -0,0 +1,500 @@
+use anyhow::Result;
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::influx::{InfluxClient, RetentionPolicy};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+#[derive(Debug, Clone)]
+pub struct FieldInfo {
+    pub name: String,
+    /// Every type the field was reported with; more than one means a conflict across shards
+    pub types: Vec<String>,
+}
+
+impl FieldInfo {
+    pub fn has_conflict(&self) -> bool {
+        self.types.len() > 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MeasurementSchema {
+    pub database: String,
+    pub measurement: String,
+    pub fields: Vec<FieldInfo>,
+    /// Tag keys with their value count; whole-database schemas leave it out
+    pub tags: Vec<(String, Option<u64>)>,
+    pub retention_policies: Vec<String>,
+}
+
+/// Collects field types, tag keys with their value counts and the RPs holding data for one measurement.
+pub async fn load_measurement_schema(
+    client: &InfluxClient,
+    db: &str,
+    measurement: &str,
+    retention_policies: &[String],
+) -> Result<MeasurementSchema> {
+    let mut fields: Vec<FieldInfo> = Vec::new();
+    let mut present_in = Vec::new();
+
+    let mut add_field = |name: String, field_type: String| {
+        match fields.iter_mut().find(|f| f.name == name) {
+            Some(f) if !f.types.contains(&field_type) => f.types.push(field_type),
+            Some(_) => {}
+            None => fields.push(FieldInfo { name, types: vec![field_type] }),
+        }
+    };
+
+    if retention_policies.is_empty() {
+        for (name, field_type) in client.show_field_keys(db, None, measurement).await? {
+            add_field(name, field_type);
+        }
+    } else {
+        // Field keys come from the shards, so asking per RP shows where the measurement lives
+        for rp in retention_policies {
+            let keys = client.show_field_keys(db, Some(rp), measurement).await?;
+            if !keys.is_empty() {
+                present_in.push(rp.clone());
+            }
+            for (name, field_type) in keys {
+                add_field(name, field_type);
+            }
+        }
+    }
+
+    let mut tags = Vec::new();
+    for key in client.show_tag_keys(db, measurement).await? {
+        let count = client.tag_values_cardinality(db, measurement, &key, false).await?;
+        tags.push((key, Some(count)));
+    }
+
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(MeasurementSchema {
+        database: db.to_string(),
+        measurement: measurement.to_string(),
+        fields,
+        tags,
+        retention_policies: present_in,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseSchema {
+    pub retention_policies: Vec<RetentionPolicy>,
+    pub measurements: Vec<MeasurementSchema>,
+}
+
+/// Schema of a whole database, without tag value counts so it stays cheap on big servers.
+pub async fn load_database_schema(client: &InfluxClient, db: &str) -> Result<DatabaseSchema> {
+    let retention_policies = client.show_retention_policies(db).await.unwrap_or_default();
+    let field_keys = client.show_all_field_keys(db).await?;
+    let tag_keys = client.show_all_tag_keys(db).await?;
+
+    let mut measurements: Vec<MeasurementSchema> = client
+        .show_measurements(db)
+        .await?
+        .into_iter()
+        .map(|measurement| MeasurementSchema {
+            database: db.to_string(),
+            measurement,
+            fields: Vec::new(),
+            tags: Vec::new(),
+            retention_policies: Vec::new(),
+        })
+        .collect();
+
+    for m in &mut measurements {
+        if let Some((_, fields)) = field_keys.iter().find(|(name, _)| name == &m.measurement) {
+            for (name, field_type) in fields {
+                match m.fields.iter_mut().find(|f| &f.name == name) {
+                    Some(f) if !f.types.contains(field_type) => f.types.push(field_type.clone()),
+                    Some(_) => {}
+                    None => m.fields.push(FieldInfo { name: name.clone(), types: vec![field_type.clone()] }),
+                }
+            }
+        }
+        if let Some((_, keys)) = tag_keys.iter().find(|(name, _)| name == &m.measurement) {
+            m.tags = keys.iter().map(|k| (k.clone(), None)).collect();
+        }
+    }
+
+    Ok(DatabaseSchema { retention_policies, measurements })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Same,
+    Added,
+    Removed,
+    Changed,
+}
+
+impl DiffKind {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            DiffKind::Same => " ",
+            DiffKind::Added => "+",
+            DiffKind::Removed => "-",
+            DiffKind::Changed => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    pub label: String,
+    pub kind: DiffKind,
+    pub detail: String,
+    pub children: Vec<DiffNode>,
+}
+
+impl DiffNode {
+    fn new(label: impl Into<String>, kind: DiffKind, detail: impl Into<String>) -> Self {
+        Self { label: label.into(), kind, detail: detail.into(), children: Vec::new() }
+    }
+
+    /// A parent is only as "same" as its children.
+    fn group(label: &str, children: Vec<DiffNode>) -> Self {
+        let kind = if children.iter().all(|c| c.kind == DiffKind::Same) {
+            DiffKind::Same
+        } else {
+            DiffKind::Changed
+        };
+        Self { label: label.to_string(), kind, detail: String::new(), children }
+    }
+
+    /// Plain-text report, one line per node, indented by depth.
+    pub fn write_report(&self, out: &mut String, depth: usize, only_differences: bool) {
+        if only_differences && self.kind == DiffKind::Same {
+            return;
+        }
+        out.push_str(&format!("{}{} {}", "  ".repeat(depth), self.kind.symbol(), self.label));
+        if !self.detail.is_empty() {
+            out.push_str(&format!(" ({})", self.detail));
+        }
+        out.push('\n');
+        for child in &self.children {
+            child.write_report(out, depth + 1, only_differences);
+        }
+    }
+}
+
+/// Diff from `left` to `right`: "added" means only present on the right.
+pub fn diff_schemas(left: &DatabaseSchema, right: &DatabaseSchema) -> Vec<DiffNode> {
+    let mut rps = Vec::new();
+    for name in union(
+        left.retention_policies.iter().map(|rp| &rp.name),
+        right.retention_policies.iter().map(|rp| &rp.name),
+    ) {
+        let l = left.retention_policies.iter().find(|rp| rp.name == name);
+        let r = right.retention_policies.iter().find(|rp| rp.name == name);
+        rps.push(match (l, r) {
+            (Some(_), None) => DiffNode::new(name, DiffKind::Removed, ""),
+            (None, Some(_)) => DiffNode::new(name, DiffKind::Added, ""),
+            (Some(l), Some(r)) => {
+                let describe = |rp: &RetentionPolicy| {
+                    format!(
+                        "duration {}, shard {}, replication {}{}",
+                        rp.duration,
+                        rp.shard_group_duration,
+                        rp.replication,
+                        if rp.default { ", default" } else { "" }
+                    )
+                };
+                let (dl, dr) = (describe(l), describe(r));
+                if dl == dr {
+                    DiffNode::new(name, DiffKind::Same, dl)
+                } else {
+                    DiffNode::new(name, DiffKind::Changed, format!("{} -> {}", dl, dr))
+                }
+            }
+            (None, None) => continue,
+        });
+    }
+
+    let mut measurements = Vec::new();
+    for name in union(
+        left.measurements.iter().map(|m| &m.measurement),
+        right.measurements.iter().map(|m| &m.measurement),
+    ) {
+        let l = left.measurements.iter().find(|m| m.measurement == name);
+        let r = right.measurements.iter().find(|m| m.measurement == name);
+        measurements.push(match (l, r) {
+            (Some(_), None) => DiffNode::new(name, DiffKind::Removed, ""),
+            (None, Some(_)) => DiffNode::new(name, DiffKind::Added, ""),
+            (Some(l), Some(r)) => {
+                let mut children = Vec::new();
+                for field in union(l.fields.iter().map(|f| &f.name), r.fields.iter().map(|f| &f.name)) {
+                    let types = |m: &MeasurementSchema| {
+                        m.fields.iter().find(|f| f.name == field).map(|f| f.types.join("/"))
+                    };
+                    let label = format!("field {}", field);
+                    children.push(match (types(l), types(r)) {
+                        (Some(t), None) => DiffNode::new(label, DiffKind::Removed, t),
+                        (None, Some(t)) => DiffNode::new(label, DiffKind::Added, t),
+                        (Some(a), Some(b)) if a != b => {
+                            DiffNode::new(label, DiffKind::Changed, format!("{} -> {}", a, b))
+                        }
+                        (Some(t), Some(_)) => DiffNode::new(label, DiffKind::Same, t),
+                        (None, None) => continue,
+                    });
+                }
+                for tag in union(l.tags.iter().map(|t| &t.0), r.tags.iter().map(|t| &t.0)) {
+                    let has = |m: &MeasurementSchema| m.tags.iter().any(|t| t.0 == tag);
+                    let label = format!("tag {}", tag);
+                    children.push(match (has(l), has(r)) {
+                        (true, false) => DiffNode::new(label, DiffKind::Removed, ""),
+                        (false, true) => DiffNode::new(label, DiffKind::Added, ""),
+                        _ => DiffNode::new(label, DiffKind::Same, ""),
+                    });
+                }
+                DiffNode::group(&name, children)
+            }
+            (None, None) => continue,
+        });
+    }
+
+    vec![
+        DiffNode::group("Retention policies", rps),
+        DiffNode::group("Measurements", measurements),
+    ]
+}
+
+fn union<'a>(a: impl Iterator<Item = &'a String>, b: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut names: Vec<String> = a.chain(b).cloned().collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_schema_tab(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        let Some(measurement) = state.selected_measurement.clone() else {
+            ui.label("Select a measurement to see its schema");
+            return;
+        };
+
+        let key = (state.active_connection.unwrap_or_default(), state.selected_db.clone().unwrap_or_default(), measurement);
+        let requested = state.schema_requested.as_ref() == Some(&key);
+        let is_current = requested
+            && state
+                .schema
+                .as_ref()
+                .map(|s| s.database == key.1 && s.measurement == key.2)
+                .unwrap_or(false);
+
+        ui.horizontal(|ui| {
+            // Load once per selection; a failed load waits for Refresh instead of retrying every frame
+            if ui.button("Refresh").clicked() || !requested {
+                state.schema_requested = Some(key.clone());
+                self.load_schema(ui.ctx(), state);
+            }
+            if state.schema_loading {
+                ui.spinner();
+            }
+        });
+
+        let Some(schema) = state.schema.as_ref().filter(|_| is_current) else {
+            return;
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let conflicts = schema.fields.iter().filter(|f| f.has_conflict()).count();
+            if conflicts > 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{} field(s) have conflicting types across shards", conflicts),
+                );
+            }
+
+            ui.heading(format!("Fields ({})", schema.fields.len()));
+            egui::Grid::new("schema_fields").striped(true).num_columns(2).show(ui, |ui| {
+                ui.strong("Field");
+                ui.strong("Type");
+                ui.end_row();
+
+                for field in &schema.fields {
+                    let types = field.types.join(", ");
+                    if field.has_conflict() {
+                        ui.colored_label(ui.visuals().warn_fg_color, &field.name);
+                        ui.colored_label(ui.visuals().warn_fg_color, types)
+                            .on_hover_text("Queries touching this field may fail or return partial data");
+                    } else {
+                        ui.label(&field.name);
+                        ui.label(types);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.heading(format!("Tags ({})", schema.tags.len()));
+            egui::Grid::new("schema_tags").striped(true).num_columns(2).show(ui, |ui| {
+                ui.strong("Tag key");
+                ui.strong("Values");
+                ui.end_row();
+
+                for (key, count) in &schema.tags {
+                    ui.label(key);
+                    ui.label(count.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string()));
+                    ui.end_row();
+                }
+            });
+
+            if !schema.retention_policies.is_empty() {
+                ui.add_space(10.0);
+                ui.heading("Retention policies");
+                for rp in &schema.retention_policies {
+                    ui.label(rp);
+                }
+            }
+        });
+    }
+
+    fn load_schema(&self, ctx: &egui::Context, state: &mut AppState) {
+        let (Some(key), Some(client)) = (state.schema_requested.clone(), state.client()) else {
+            return;
+        };
+        let rps: Vec<String> = state.retention_policies().iter().map(|rp| rp.name.clone()).collect();
+
+        state.schema_loading = true;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            Self::fetch_schema(state_clone, ctx_clone, client, key, rps).await;
+        });
+    }
+
+    async fn fetch_schema(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        client: InfluxClient,
+        key: (u64, String, String),
+        rps: Vec<String>,
+    ) {
+        let result = load_measurement_schema(&client, &key.1, &key.2, &rps).await;
+
+        {
+            let mut state = state.lock().unwrap();
+            // Another connection or measurement was selected meanwhile
+            if state.schema_requested.as_ref() != Some(&key) {
+                return;
+            }
+            state.schema_loading = false;
+            match result {
+                Ok(schema) => state.schema = Some(schema),
+                Err(e) => state.status = format!("Error: {}", e),
+            }
+        }
+        ctx.request_repaint();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Measurement<'a> = (&'a str, &'a [(&'a str, &'a str)], &'a [&'a str]);
+
+    fn schema(rps: &[(&str, &str)], measurements: &[Measurement]) -> DatabaseSchema {
+        DatabaseSchema {
+            retention_policies: rps
+                .iter()
+                .map(|(name, duration)| RetentionPolicy {
+                    name: name.to_string(),
+                    duration: duration.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            measurements: measurements
+                .iter()
+                .map(|(name, fields, tags)| MeasurementSchema {
+                    database: "db".to_string(),
+                    measurement: name.to_string(),
+                    fields: fields
+                        .iter()
+                        .map(|(f, t)| FieldInfo { name: f.to_string(), types: t.split('/').map(String::from).collect() })
+                        .collect(),
+                    tags: tags.iter().map(|t| (t.to_string(), None)).collect(),
+                    retention_policies: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Every node that differs, as `path kind detail`.
+    fn differences(nodes: &[DiffNode], path: &str, out: &mut Vec<String>) {
+        for node in nodes {
+            let path = if path.is_empty() { node.label.clone() } else { format!("{}/{}", path, node.label) };
+            if node.kind != DiffKind::Same && node.children.is_empty() {
+                out.push(format!("{} {} {}", path, node.kind.symbol(), node.detail).trim_end().to_string());
+            }
+            differences(&node.children, &path, out);
+        }
+    }
+
+    #[test]
+    fn diff_schemas_cases() {
+        let cpu: Measurement = ("cpu", &[("usage", "float")], &["host"]);
+        let cases: Vec<(&str, DatabaseSchema, DatabaseSchema, Vec<&str>)> = vec![
+            (
+                "identical",
+                schema(&[("autogen", "0s")], &[cpu]),
+                schema(&[("autogen", "0s")], &[cpu]),
+                vec![],
+            ),
+            (
+                "measurement added and removed",
+                schema(&[], &[cpu, ("mem", &[], &[])]),
+                schema(&[], &[cpu, ("disk", &[], &[])]),
+                vec!["Measurements/disk +", "Measurements/mem -"],
+            ),
+            (
+                "field and tag added and removed",
+                schema(&[], &[("cpu", &[("usage", "float"), ("idle", "float")], &["host"])]),
+                schema(&[], &[("cpu", &[("usage", "float"), ("steal", "float")], &["host", "region"])]),
+                vec![
+                    "Measurements/cpu/field idle - float",
+                    "Measurements/cpu/field steal + float",
+                    "Measurements/cpu/tag region +",
+                ],
+            ),
+            (
+                "field type changed",
+                schema(&[], &[("cpu", &[("usage", "float")], &[])]),
+                schema(&[], &[("cpu", &[("usage", "integer")], &[])]),
+                vec!["Measurements/cpu/field usage ~ float -> integer"],
+            ),
+            (
+                "field type conflict on one side",
+                schema(&[], &[("cpu", &[("usage", "float")], &[])]),
+                schema(&[], &[("cpu", &[("usage", "float/integer")], &[])]),
+                vec!["Measurements/cpu/field usage ~ float -> float/integer"],
+            ),
+            (
+                "retention policies",
+                schema(&[("autogen", "0s"), ("old", "1w")], &[]),
+                schema(&[("autogen", "4w"), ("new", "1d")], &[]),
+                vec![
+                    "Retention policies/autogen ~ duration 0s, shard , replication 0 -> duration 4w, shard , replication 0",
+                    "Retention policies/new +",
+                    "Retention policies/old -",
+                ],
+            ),
+        ];
+
+        for (name, left, right, expected) in cases {
+            let diff = diff_schemas(&left, &right);
+            let mut found = Vec::new();
+            differences(&diff, "", &mut found);
+            assert_eq!(found, expected, "{}", name);
+
+            let changed = diff.iter().any(|n| n.kind != DiffKind::Same);
+            assert_eq!(changed, !expected.is_empty(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerFlavor {
+    InfluxDb1,
+    InfluxDb1Enterprise,
+    InfluxDb2,
+    InfluxDb3Core,
+    InfluxDb3Enterprise,
+    /// Answers `/ping` but doesn't identify as InfluxDB (VictoriaMetrics, proxies, ...)
+    Compatible,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub flavor: ServerFlavor,
+    pub version: String,
+    pub build: String,
+    /// Whether `/api/v2/query` answers Flux; `None` until probed or when the probe was inconclusive
+    pub flux_enabled: Option<bool>,
+}
+
+/// What the UI may offer for a given server.
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub influxql: bool,
+    pub flux: bool,
+    pub sql: bool,
+    pub retention_policies: bool,
+    pub manage_databases: bool,
+    pub select_into: bool,
+    pub continuous_queries: bool,
+    pub diagnostics: bool,
+    pub kill_query: bool,
+}
+
+impl Capabilities {
+    /// Used until a server has been detected, so nothing gets hidden by mistake.
+    pub const ALL: Capabilities = Capabilities {
+        influxql: true,
+        flux: true,
+        sql: true,
+        retention_policies: true,
+        manage_databases: true,
+        select_into: true,
+        continuous_queries: true,
+        diagnostics: true,
+        kill_query: true,
+    };
+
+    pub fn summary(&self) -> String {
+        let flag = |b: bool| if b { "yes" } else { "no" };
+        format!(
+            "InfluxQL: {}\nFlux: {}\nSQL: {}\nRetention policies: {}\nDatabase admin: {}\nSELECT INTO: {}\nContinuous queries: {}\nDiagnostics: {}\nKILL QUERY: {}",
+            flag(self.influxql),
+            flag(self.flux),
+            flag(self.sql),
+            flag(self.retention_policies),
+            flag(self.manage_databases),
+            flag(self.select_into),
+            flag(self.continuous_queries),
+            flag(self.diagnostics),
+            flag(self.kill_query),
+        )
+    }
+}
+
+impl ServerInfo {
+    /// Builds server info from the `X-Influxdb-Version` / `X-Influxdb-Build` headers of `/ping`.
+    pub fn from_ping(version: Option<&str>, build: Option<&str>) -> Self {
+        let version = version.unwrap_or("").trim();
+        // 2.x reports `v2.7.4`; only a `v` before a digit is a prefix
+        let version = version
+            .strip_prefix('v')
+            .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(version)
+            .to_string();
+        let build = build.unwrap_or("").trim().to_string();
+        let major = version_part(&version, 0);
+
+        let flavor = match (major, build.to_ascii_lowercase().as_str()) {
+            (None, _) => ServerFlavor::Compatible,
+            (Some(3), "enterprise") => ServerFlavor::InfluxDb3Enterprise,
+            (Some(3), _) => ServerFlavor::InfluxDb3Core,
+            (Some(2), _) => ServerFlavor::InfluxDb2,
+            (Some(_), "ent") | (Some(_), "enterprise") => ServerFlavor::InfluxDb1Enterprise,
+            (Some(_), _) => ServerFlavor::InfluxDb1,
+        };
+
+        Self { flavor, version, build, flux_enabled: None }
+    }
+
+    /// 1.7+ only serves Flux with `flux-enabled = true`, which is off by default, so it has to be asked.
+    pub fn needs_flux_probe(&self) -> bool {
+        matches!(self.flavor, ServerFlavor::InfluxDb1 | ServerFlavor::InfluxDb1Enterprise)
+            && self.flux_enabled.is_none()
+            && self.capabilities().flux
+    }
+
+    pub fn label(&self) -> String {
+        let name = match self.flavor {
+            ServerFlavor::InfluxDb1 => "InfluxDB OSS",
+            ServerFlavor::InfluxDb1Enterprise => "InfluxDB Enterprise",
+            ServerFlavor::InfluxDb2 => "InfluxDB 2",
+            ServerFlavor::InfluxDb3Core => "InfluxDB 3 Core",
+            ServerFlavor::InfluxDb3Enterprise => "InfluxDB 3 Enterprise",
+            ServerFlavor::Compatible => "InfluxDB-compatible server",
+        };
+
+        if self.version.is_empty() {
+            name.to_string()
+        } else {
+            format!("{} {}", name, self.version)
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        match self.flavor {
+            ServerFlavor::InfluxDb1 | ServerFlavor::InfluxDb1Enterprise => Capabilities {
+                influxql: true,
+                // Flux arrived in 1.7 behind flux-enabled; offered until a probe says it's off
+                flux: version_part(&self.version, 1).unwrap_or(0) >= 7 && self.flux_enabled != Some(false),
+                sql: false,
+                retention_policies: true,
+                manage_databases: true,
+                select_into: true,
+                continuous_queries: true,
+                diagnostics: true,
+                kill_query: true,
+            },
+            ServerFlavor::InfluxDb2 => Capabilities {
+                influxql: true,
+                flux: true,
+                sql: false,
+                retention_policies: false,
+                manage_databases: false,
+                select_into: false,
+                continuous_queries: false,
+                diagnostics: false,
+                kill_query: false,
+            },
+            ServerFlavor::InfluxDb3Core | ServerFlavor::InfluxDb3Enterprise => Capabilities {
+                influxql: true,
+                flux: false,
+                sql: true,
+                retention_policies: false,
+                manage_databases: false,
+                select_into: false,
+                continuous_queries: false,
+                diagnostics: false,
+                kill_query: false,
+            },
+            ServerFlavor::Compatible => Capabilities {
+                influxql: true,
+                flux: false,
+                sql: false,
+                retention_policies: false,
+                manage_databases: false,
+                select_into: false,
+                continuous_queries: false,
+                diagnostics: false,
+                kill_query: false,
+            },
+        }
+    }
+}
+
+fn version_part(version: &str, index: usize) -> Option<u32> {
+    version
+        .split(['.', '-', ' '])
+        .nth(index)
+        .and_then(|p| p.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ping_cases() {
+        let cases = [
+            (Some("1.8.10"), Some("OSS"), ServerFlavor::InfluxDb1, "1.8.10"),
+            (Some("1.11.3-c1.11.3"), Some("ENT"), ServerFlavor::InfluxDb1Enterprise, "1.11.3-c1.11.3"),
+            (Some("v2.7.4"), Some("OSS"), ServerFlavor::InfluxDb2, "2.7.4"),
+            (Some(" 3.0.1 "), None, ServerFlavor::InfluxDb3Core, "3.0.1"),
+            (Some("3.1.0"), Some("Enterprise"), ServerFlavor::InfluxDb3Enterprise, "3.1.0"),
+            (Some("victoria-metrics"), None, ServerFlavor::Compatible, "victoria-metrics"),
+            (None, None, ServerFlavor::Compatible, ""),
+        ];
+
+        for (version, build, flavor, parsed) in cases {
+            let info = ServerInfo::from_ping(version, build);
+            assert_eq!(info.flavor, flavor, "{:?} {:?}", version, build);
+            assert_eq!(info.version, parsed);
+            assert_eq!(info.flux_enabled, None);
+        }
+    }
+
+    #[test]
+    fn flux_on_1x_depends_on_version_and_probe() {
+        let old = ServerInfo::from_ping(Some("1.6.6"), None);
+        assert!(!old.capabilities().flux);
+        assert!(!old.needs_flux_probe());
+
+        let mut info = ServerInfo::from_ping(Some("1.8.10"), None);
+        assert!(info.needs_flux_probe());
+        assert!(info.capabilities().flux);
+
+        info.flux_enabled = Some(false);
+        assert!(!info.capabilities().flux);
+        assert!(!info.needs_flux_probe());
+
+        assert!(!ServerInfo::from_ping(Some("2.7.4"), None).needs_flux_probe());
+    }
+}
@@ -0,0 +1,215 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::connections::{Connection, ConnectionStatus};
+use crate::credentials::{self, CredentialRef, CredentialStore, VaultPrompt, VaultRequest};
+use crate::influx::{InfluxClient, SqlFormat};
+use crate::ui::{AppState, ChartSettings, DataSource, QueryLanguage};
+use crate::workspace::WorkspaceTab;
+use crate::InfluxDBApp;
+
+// Key in eframe's storage; panel sizes, column widths, window layout and theme are kept by egui itself
+pub const SESSION_KEY: &str = "session";
+
+#[derive(Serialize, Deserialize)]
+struct SavedConnection {
+    host: String,
+    proxy: Option<String>,
+    /// Never the secret itself
+    #[serde(default)]
+    credential: Option<CredentialRef>,
+    #[serde(default)]
+    read_only: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTab {
+    title: String,
+    renamed: bool,
+    host: Option<String>,
+    database: Option<String>,
+    source: Option<DataSource>,
+    executed_query: Option<String>,
+    custom_query: String,
+    query_language: QueryLanguage,
+    sql_format: SqlFormat,
+    flux_flatten: bool,
+    chart: ChartSettings,
+}
+
+/// Everything restored on the next launch. Results aren't stored, they are queried again if the user opted in.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    host: String,
+    proxy: String,
+    connections: Vec<SavedConnection>,
+    active_connection: Option<String>,
+    selected_db: Option<String>,
+    selected_rp: Option<String>,
+    selected_measurement: Option<String>,
+    selected_series: Option<String>,
+    series_filter: String,
+    tabs: Vec<SavedTab>,
+    active_tab: usize,
+    rerun_queries: bool,
+}
+
+impl Session {
+    pub fn capture(state: &AppState) -> Self {
+        Self {
+            host: state.host.clone(),
+            proxy: state.proxy.clone(),
+            connections: state
+                .connections
+                .iter()
+                .map(|c| SavedConnection {
+                    host: c.host.clone(),
+                    proxy: c.proxy.clone(),
+                    credential: c.credential.clone(),
+                    read_only: c.read_only,
+                })
+                .collect(),
+            active_connection: state.connection().map(|c| c.host.clone()),
+            selected_db: state.selected_db.clone(),
+            selected_rp: state.selected_rp.clone(),
+            selected_measurement: state.selected_measurement.clone(),
+            selected_series: state.selected_series.clone(),
+            series_filter: state.series_filter.clone(),
+            tabs: state
+                .tabs
+                .iter()
+                .map(|tab| SavedTab {
+                    title: tab.title.clone(),
+                    renamed: tab.renamed,
+                    host: tab.host().map(String::from),
+                    database: tab.database.clone(),
+                    source: tab.source.clone(),
+                    executed_query: tab.executed_query.clone(),
+                    custom_query: tab.custom_query.clone(),
+                    query_language: tab.query_language,
+                    sql_format: tab.sql_format,
+                    flux_flatten: tab.flux_flatten,
+                    chart: tab.chart.clone(),
+                })
+                .collect(),
+            active_tab: state.tabs.iter().position(|t| t.id == state.active_tab).unwrap_or(0),
+            rerun_queries: state.rerun_on_startup,
+        }
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn restore_session(&self, ctx: &egui::Context, session: Session) {
+        let mut state = self.state.lock().unwrap();
+        state.host = session.host;
+        state.proxy = session.proxy;
+        state.rerun_on_startup = session.rerun_queries;
+
+        let mut locked = Vec::new();
+        for connection in session.connections {
+            let read_only = connection.read_only;
+            let id = match connection.credential {
+                // Vault credentials wait for the passphrase, the connection is listed meanwhile
+                Some(reference) if reference.store == CredentialStore::Vault => {
+                    state.connection_counter += 1;
+                    let mut locked_connection = Connection::new(state.connection_counter, connection.host, connection.proxy, None);
+                    locked_connection.status = ConnectionStatus::Locked;
+                    locked_connection.credential = Some(reference);
+                    locked.push(locked_connection.id);
+                    state.connections.push(locked_connection);
+                    state.connection_counter
+                }
+                Some(reference) => {
+                    let credentials = credentials::load(&reference, None);
+                    if let Err(e) = &credentials {
+                        state.status = format!("Credentials for {} not found in the keyring: {}", connection.host, e);
+                    }
+                    self.connect(ctx, &mut state, connection.host, connection.proxy, credentials.ok(), Some(reference))
+                }
+                None => self.connect(ctx, &mut state, connection.host, connection.proxy, None, None),
+            };
+            if let Some(connection) = state.connection_by_id(id) {
+                connection.read_only = read_only;
+            }
+        }
+        if !locked.is_empty() {
+            state.vault_prompt = Some(VaultPrompt::new(VaultRequest::Unlock(locked)));
+        }
+        let active = session
+            .active_connection
+            .and_then(|host| state.connections.iter().find(|c| c.host == host).map(|c| c.id));
+        if let Some(id) = active {
+            state.select_connection(id);
+        }
+        state.selected_db = session.selected_db;
+        state.selected_rp = session.selected_rp;
+        state.selected_measurement = session.selected_measurement;
+        state.selected_series = session.selected_series;
+        state.series_filter = session.series_filter;
+
+        if !session.tabs.is_empty() {
+            let tabs: Vec<WorkspaceTab> = session
+                .tabs
+                .into_iter()
+                .enumerate()
+                .map(|(i, saved)| {
+                    let mut tab = WorkspaceTab::new(i as u64 + 1, saved.title);
+                    tab.renamed = saved.renamed;
+                    tab.client = saved.host.map(|host| {
+                        state
+                            .connections
+                            .iter()
+                            .find(|c| c.host == host)
+                            .map(|c| c.client.clone())
+                            .unwrap_or_else(|| InfluxClient::new(host, None))
+                    });
+                    tab.database = saved.database;
+                    tab.source = saved.source;
+                    tab.executed_query = saved.executed_query;
+                    tab.custom_query = saved.custom_query;
+                    tab.query_language = saved.query_language;
+                    tab.sql_format = saved.sql_format;
+                    tab.flux_flatten = saved.flux_flatten;
+                    tab.chart = saved.chart;
+                    if tab.source.is_some() || tab.executed_query.is_some() {
+                        tab.status = "Restored, not re-run".to_string();
+                    }
+                    tab
+                })
+                .collect();
+            state.tab_counter = tabs.len() as u64;
+            state.active_tab = tabs[session.active_tab.min(tabs.len() - 1)].id;
+            state.tabs = tabs;
+        }
+
+        if let (Some(id), Some(db)) = (state.active_connection, state.selected_db.clone()) {
+            self.load_database(ctx, &mut state, id, db, true);
+        }
+        if let (Some(client), Some(source)) = (state.client(), state.data_source()) {
+            let state_clone = Arc::clone(&self.state);
+            let ctx_clone = ctx.clone();
+            let filter = state.series_filter.clone();
+            self.runtime.spawn(async move {
+                Self::load_series(state_clone, ctx_clone, client, source, filter, 0).await;
+            });
+        }
+
+        if !state.rerun_on_startup {
+            return;
+        }
+        for tab in &mut state.tabs {
+            if let (Some(client), Some(source)) = (tab.client.clone(), tab.source.clone()) {
+                let state_clone = Arc::clone(&self.state);
+                let ctx_clone = ctx.clone();
+                let tab_id = tab.id;
+                self.runtime.spawn(async move {
+                    Self::load_chunk(state_clone, ctx_clone, tab_id, client, source, 0).await;
+                });
+            } else if let Some(query) = tab.executed_query.clone() {
+                self.run_tab_query(ctx, tab, query);
+            }
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use eframe::egui::{self, Color32, Pos2, Rect, Stroke};
+use std::sync::{Arc, Mutex};
+
+use crate::chart;
+use crate::influx::{self, InfluxClient, Shard, ShardGroup};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+const DAY: i64 = 86_400;
+// More groups than this, each shorter than a day, is worth a warning
+const SMALL_GROUP_LIMIT: usize = 50;
+
+pub struct GroupRow {
+    pub group: ShardGroup,
+    pub start: i64,
+    pub end: i64,
+    pub expiry: i64,
+    pub shards: Vec<Shard>,
+    pub flags: Vec<String>,
+}
+
+pub struct PolicyShards {
+    pub database: String,
+    pub retention_policy: String,
+    pub groups: Vec<GroupRow>,
+    pub flags: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct ShardsView {
+    /// Connection the window was opened on; drops go there even after another one is selected
+    pub connection: u64,
+    pub policies: Vec<PolicyShards>,
+    pub database: Option<String>,
+    pub only_flagged: bool,
+    /// Shard about to be dropped, and the typed confirmation
+    pub confirm_drop: Option<(u64, String)>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+fn parse_secs(time: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|t| t.timestamp())
+        .unwrap_or(0)
+}
+
+fn secs_label(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Groups shards under their shard groups per database/RP and flags what looks wrong.
+fn build_policies(
+    groups: Vec<ShardGroup>,
+    shards: Vec<Shard>,
+    durations: &[(String, String, i64, i64)],
+    now: i64,
+) -> Vec<PolicyShards> {
+    let mut policies: Vec<PolicyShards> = Vec::new();
+
+    for group in groups {
+        let (start, end, expiry) = (
+            parse_secs(&group.start_time),
+            parse_secs(&group.end_time),
+            parse_secs(&group.expiry_time),
+        );
+        let rp = durations
+            .iter()
+            .find(|(db, rp, _, _)| db == &group.database && rp == &group.retention_policy);
+
+        let mut flags = Vec::new();
+        if let Some((_, _, duration, shard_duration)) = rp {
+            // Infinite retention reports expiry == end
+            if *duration > 0 && expiry < now {
+                flags.push("past the RP duration".to_string());
+            }
+            if *shard_duration > 0 && end - start != *shard_duration {
+                flags.push(format!(
+                    "spans {}s, RP shard duration is {}s",
+                    end - start,
+                    shard_duration
+                ));
+            }
+        }
+        if start > now + DAY {
+            flags.push("starts in the future".to_string());
+        }
+
+        let group_shards: Vec<Shard> = shards.iter().filter(|s| s.shard_group == group.id).cloned().collect();
+        if group_shards.is_empty() {
+            flags.push("no shards".to_string());
+        }
+
+        let row = GroupRow {
+            start,
+            end,
+            expiry,
+            shards: group_shards,
+            flags,
+            group,
+        };
+
+        match policies
+            .iter_mut()
+            .find(|p| p.database == row.group.database && p.retention_policy == row.group.retention_policy)
+        {
+            Some(policy) => policy.groups.push(row),
+            None => policies.push(PolicyShards {
+                database: row.group.database.clone(),
+                retention_policy: row.group.retention_policy.clone(),
+                groups: vec![row],
+                flags: Vec::new(),
+            }),
+        }
+    }
+
+    for policy in &mut policies {
+        policy.groups.sort_by_key(|g| g.start);
+        let small = policy.groups.iter().filter(|g| g.end - g.start < DAY).count();
+        if small > SMALL_GROUP_LIMIT {
+            policy.flags.push(format!(
+                "{} shard groups shorter than a day; a longer shard duration would help",
+                small
+            ));
+        }
+        let flagged = policy.groups.iter().filter(|g| !g.flags.is_empty()).count();
+        if flagged > 0 {
+            policy.flags.push(format!("{} flagged shard groups", flagged));
+        }
+    }
+    policies
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_shards_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.shards.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(connection) = state.connection_of(id) else {
+            state.shards = None;
+            return;
+        };
+        let client = connection.client.clone();
+        let can_drop = !connection.read_only && connection.capabilities().manage_databases;
+        let Some(view) = &mut state.shards else {
+            return;
+        };
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut drop_shard = None;
+
+        egui::Window::new(format!("Shards - {}", client.host()))
+            .id(egui::Id::new("shards_window"))
+            .open(&mut open)
+            .default_size([820.0, 560.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                    let mut databases: Vec<String> = view.policies.iter().map(|p| p.database.clone()).collect();
+                    databases.dedup();
+                    egui::ComboBox::from_id_salt("shards_database")
+                        .selected_text(view.database.as_deref().unwrap_or("All databases"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut view.database, None, "All databases");
+                            for db in databases {
+                                ui.selectable_value(&mut view.database, Some(db.clone()), db);
+                            }
+                        });
+                    ui.checkbox(&mut view.only_flagged, "Only flagged");
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                if let Some((id, confirm)) = &mut view.confirm_drop {
+                    let mut done = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Drop shard {}? Its data is deleted. Type the shard id to confirm:", id));
+                        ui.add(egui::TextEdit::singleline(confirm).desired_width(80.0));
+                        if ui.add_enabled(*confirm == id.to_string(), egui::Button::new("Drop")).clicked() {
+                            drop_shard = Some(*id);
+                            done = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            done = true;
+                        }
+                    });
+                    if done {
+                        view.confirm_drop = None;
+                    }
+                }
+
+                ui.separator();
+
+                let now = chrono::Utc::now().timestamp();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for policy in &view.policies {
+                        if view.database.as_ref().is_some_and(|db| db != &policy.database) {
+                            continue;
+                        }
+                        if view.only_flagged && policy.flags.is_empty() {
+                            continue;
+                        }
+
+                        let mut title = egui::RichText::new(format!(
+                            "{}.{} ({} groups)",
+                            policy.database,
+                            policy.retention_policy,
+                            policy.groups.len()
+                        ));
+                        if !policy.flags.is_empty() {
+                            title = title.color(ui.visuals().warn_fg_color);
+                        }
+
+                        egui::CollapsingHeader::new(title)
+                            .id_salt(("shards", &policy.database, &policy.retention_policy))
+                            .default_open(!policy.flags.is_empty())
+                            .show(ui, |ui| {
+                                for flag in &policy.flags {
+                                    ui.colored_label(ui.visuals().warn_fg_color, flag);
+                                }
+                                timeline(ui, &policy.groups, now);
+                                group_table(ui, policy, view.only_flagged, can_drop, &mut view.confirm_drop);
+                            });
+                    }
+                });
+            });
+
+        if !open {
+            state.shards = None;
+            return;
+        }
+
+        if !refresh && drop_shard.is_none() {
+            return;
+        }
+
+        view.loading = true;
+        view.error = None;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            if let Some(id) = drop_shard {
+                let result = client.drop_shard(id).await;
+                let mut state = state_clone.lock().unwrap();
+                state.status = match result {
+                    Ok(()) => format!("Dropped shard {}", id),
+                    Err(e) => format!("Error: {}", e),
+                };
+            }
+            Self::load_shards(state_clone, ctx_clone, client).await;
+        });
+    }
+
+    pub(crate) async fn load_shards(state: Arc<Mutex<AppState>>, ctx: egui::Context, client: InfluxClient) {
+        let result = async {
+            let groups = client.show_shard_groups().await?;
+            let shards = client.show_shards().await?;
+
+            let mut databases: Vec<String> = groups.iter().map(|g| g.database.clone()).collect();
+            databases.sort();
+            databases.dedup();
+
+            let mut durations = Vec::new();
+            for db in databases {
+                for rp in client.show_retention_policies(&db).await? {
+                    let duration = influx::parse_go_duration(&rp.duration).unwrap_or(0) / 1_000_000_000;
+                    let shard = influx::parse_go_duration(&rp.shard_group_duration).unwrap_or(0) / 1_000_000_000;
+                    durations.push((db.clone(), rp.name, duration, shard));
+                }
+            }
+
+            anyhow::Ok(build_policies(groups, shards, &durations, chrono::Utc::now().timestamp()))
+        }
+        .await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.shards {
+                view.loading = false;
+                match result {
+                    Ok(policies) => view.policies = policies,
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// Shard groups as bars on a shared time axis, flagged ones in the warning color.
+fn timeline(ui: &mut egui::Ui, groups: &[GroupRow], now: i64) {
+    let (Some(first), Some(last)) = (groups.iter().map(|g| g.start).min(), groups.iter().map(|g| g.end).max()) else {
+        return;
+    };
+    let (first, last) = (first.min(now), last.max(now));
+    let span = (last - first).max(1) as f32;
+
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 36.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let x = |t: i64| rect.left() + (t - first) as f32 / span * rect.width();
+    let mut hovered = None;
+    for group in groups {
+        let bar = Rect::from_min_max(
+            Pos2::new(x(group.start), rect.top() + 6.0),
+            Pos2::new(x(group.end).max(x(group.start) + 1.0), rect.bottom() - 14.0),
+        );
+        let color = if group.flags.is_empty() {
+            chart::color(0)
+        } else {
+            visuals.warn_fg_color
+        };
+        painter.rect_filled(bar, 0.0, color);
+        painter.rect_stroke(bar, 0.0, Stroke::new(1.0, visuals.extreme_bg_color));
+        if response.hover_pos().is_some_and(|p| bar.x_range().contains(p.x)) {
+            hovered = Some(group);
+        }
+    }
+
+    // The current time, for spotting expired and future groups
+    painter.line_segment(
+        [Pos2::new(x(now), rect.top()), Pos2::new(x(now), rect.bottom() - 12.0)],
+        Stroke::new(1.0, Color32::from_rgb(220, 80, 80)),
+    );
+    let font = egui::FontId::proportional(10.0);
+    painter.text(rect.left_bottom(), egui::Align2::LEFT_BOTTOM, secs_label(first), font.clone(), visuals.weak_text_color());
+    painter.text(rect.right_bottom(), egui::Align2::RIGHT_BOTTOM, secs_label(last), font, visuals.weak_text_color());
+
+    if let Some(group) = hovered {
+        response.on_hover_ui_at_pointer(|ui| {
+            ui.label(format!("Shard group {}", group.group.id));
+            ui.label(format!("{} .. {}", group.group.start_time, group.group.end_time));
+            ui.label(format!("Expires {}", group.group.expiry_time));
+            for flag in &group.flags {
+                ui.colored_label(ui.visuals().warn_fg_color, flag);
+            }
+        });
+    }
+}
+
+fn group_table(
+    ui: &mut egui::Ui,
+    policy: &PolicyShards,
+    only_flagged: bool,
+    can_drop: bool,
+    confirm_drop: &mut Option<(u64, String)>,
+) {
+    egui::Grid::new(("shard_groups", &policy.database, &policy.retention_policy))
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Group");
+            ui.strong("Start");
+            ui.strong("End");
+            ui.strong("Expiry");
+            ui.strong("Shards (owners)");
+            ui.strong("Flags");
+            ui.end_row();
+
+            for group in &policy.groups {
+                if only_flagged && group.flags.is_empty() {
+                    continue;
+                }
+                ui.label(group.group.id.to_string());
+                ui.label(secs_label(group.start));
+                ui.label(secs_label(group.end));
+                ui.label(secs_label(group.expiry));
+
+                ui.horizontal(|ui| {
+                    for shard in &group.shards {
+                        let owners = if shard.owners.is_empty() { "-" } else { &shard.owners };
+                        ui.label(format!("{} ({})", shard.id, owners));
+                        if can_drop && ui.small_button("Drop").clicked() {
+                            *confirm_drop = Some((shard.id, String::new()));
+                        }
+                    }
+                });
+
+                ui.colored_label(ui.visuals().warn_fg_color, group.flags.join("; "));
+                ui.end_row();
+            }
+        });
+}
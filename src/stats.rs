@@ -0,0 +1,220 @@
+use eframe::egui;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Most frequent values listed per text column
+const TOP_VALUES: usize = 5;
+
+/// Running aggregates of one result column; numbers and text are tracked apart since a column can mix them.
+#[derive(Clone, Default)]
+pub struct ColumnStats {
+    pub name: String,
+    pub nulls: usize,
+    /// Kept sorted for the median and percentiles
+    numbers: Vec<f64>,
+    // Welford's running mean and sum of squared deviations
+    mean: f64,
+    m2: f64,
+    texts: HashMap<String, usize>,
+    text_count: usize,
+    top: Vec<(String, usize)>,
+}
+
+impl ColumnStats {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, value: &Value, numbers: &mut Vec<f64>) {
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Number(n) => {
+                let Some(x) = n.as_f64() else {
+                    return;
+                };
+                numbers.push(x);
+                let count = (self.numbers.len() + numbers.len()) as f64;
+                let delta = x - self.mean;
+                self.mean += delta / count;
+                self.m2 += delta * (x - self.mean);
+            }
+            Value::String(s) => {
+                self.text_count += 1;
+                *self.texts.entry(s.clone()).or_default() += 1;
+            }
+            other => {
+                self.text_count += 1;
+                *self.texts.entry(other.to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    fn finish_batch(&mut self, mut numbers: Vec<f64>) {
+        if !numbers.is_empty() {
+            numbers.sort_by(f64::total_cmp);
+            self.numbers.extend(numbers);
+            // Two sorted runs; the stable sort merges them in linear time
+            self.numbers.sort_by(f64::total_cmp);
+        }
+        if self.text_count > 0 {
+            let mut top: Vec<(String, usize)> = self.texts.iter().map(|(v, n)| (v.clone(), *n)).collect();
+            top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            top.truncate(TOP_VALUES);
+            self.top = top;
+        }
+    }
+
+    /// Non-null values.
+    pub fn count(&self) -> usize {
+        self.numbers.len() + self.text_count
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        !self.numbers.is_empty() && self.text_count == 0
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.numbers.first().copied()
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.numbers.last().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.numbers.is_empty()).then_some(self.mean)
+    }
+
+    /// Sample standard deviation, like InfluxQL's `STDDEV()`.
+    pub fn stddev(&self) -> Option<f64> {
+        let n = self.numbers.len();
+        (n > 1).then(|| (self.m2 / (n - 1) as f64).sqrt())
+    }
+
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        percentile(&self.numbers, p)
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn top_values(&self) -> &[(String, usize)] {
+        &self.top
+    }
+}
+
+/// Linearly interpolated percentile (0-100) of sorted values.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64))
+}
+
+/// Per-column statistics of a tab's result, extended with each appended chunk rather than recomputed.
+#[derive(Clone, Default)]
+pub struct ResultStats {
+    pub visible: bool,
+    rows: usize,
+    columns: Vec<String>,
+    pub stats: Vec<ColumnStats>,
+}
+
+impl ResultStats {
+    /// Forgets everything counted; for when rows were replaced or dropped rather than appended.
+    pub fn invalidate(&mut self) {
+        self.rows = 0;
+        self.columns.clear();
+        self.stats.clear();
+    }
+
+    /// Takes in the rows added since the last call.
+    pub fn update(&mut self, columns: &[String], rows: &[Vec<Value>]) {
+        if columns != self.columns.as_slice() || rows.len() < self.rows {
+            self.invalidate();
+            self.columns = columns.to_vec();
+            self.stats = columns.iter().map(|c| ColumnStats::new(c.clone())).collect();
+        }
+        if rows.len() == self.rows {
+            return;
+        }
+
+        for (i, column) in self.stats.iter_mut().enumerate() {
+            // Timestamps would only ever be distinct
+            if column.name == "time" {
+                continue;
+            }
+            let mut numbers = Vec::new();
+            for row in &rows[self.rows..] {
+                column.add(row.get(i).unwrap_or(&Value::Null), &mut numbers);
+            }
+            column.finish_batch(numbers);
+        }
+        self.rows = rows.len();
+    }
+}
+
+fn format_stat(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.fract() == 0.0 && v.abs() < 1e15 => format!("{:.0}", v),
+        Some(v) => {
+            let text = format!("{:.4}", v);
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+        None => "-".to_string(),
+    }
+}
+
+pub fn stats_table(ui: &mut egui::Ui, id: impl std::hash::Hash, stats: &ResultStats) {
+    egui::ScrollArea::both()
+        .id_salt(("stats_scroll", &id))
+        .max_height(180.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            egui::Grid::new(("stats_grid", &id)).striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                for header in [
+                    "Column", "Count", "Nulls", "Min", "Max", "Mean", "Median", "Stddev", "p95", "p99", "Distinct", "Top values",
+                ] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for column in stats.stats.iter().filter(|c| c.name != "time") {
+                    ui.label(&column.name);
+                    ui.label(column.count().to_string());
+                    ui.label(column.nulls.to_string());
+
+                    if column.is_numeric() {
+                        ui.label(format_stat(column.min()));
+                        ui.label(format_stat(column.max()));
+                        ui.label(format_stat(column.mean()));
+                        ui.label(format_stat(column.percentile(50.0)));
+                        ui.label(format_stat(column.stddev()));
+                        ui.label(format_stat(column.percentile(95.0)));
+                        ui.label(format_stat(column.percentile(99.0)));
+                        ui.weak("-");
+                        ui.weak("-");
+                    } else {
+                        for _ in 0..7 {
+                            ui.weak("-");
+                        }
+                        ui.label(column.distinct().to_string());
+                        let top = column
+                            .top_values()
+                            .iter()
+                            .map(|(value, n)| format!("{} ({})", value, n))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        ui.label(top);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+}
@@ -0,0 +1,206 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::influx::InfluxClient;
+use crate::ui::{AppState, DataSource};
+use crate::workspace::WorkspaceTab;
+use crate::InfluxDBApp;
+
+// How long freshly tailed rows stay highlighted
+const HIGHLIGHT: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct TailState {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub max_rows: usize,
+    pub source: Option<DataSource>,
+    pub last_poll: Option<Instant>,
+    pub polling: bool,
+    /// Set when the table is scrolled away from the bottom
+    pub paused: bool,
+    pub scroll_to_end: bool,
+    pub new_rows: usize,
+    pub new_since: Option<Instant>,
+}
+
+impl Default for TailState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 5,
+            max_rows: 5000,
+            source: None,
+            last_poll: None,
+            polling: false,
+            paused: false,
+            scroll_to_end: false,
+            new_rows: 0,
+            new_since: None,
+        }
+    }
+}
+
+impl TailState {
+    /// Index of the first row to highlight, while the highlight lasts.
+    pub fn highlight_from(&self, total: usize) -> Option<usize> {
+        self.new_since
+            .filter(|t| t.elapsed() < HIGHLIGHT)
+            .map(|_| total.saturating_sub(self.new_rows))
+    }
+
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.paused = false;
+        self.source = None;
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_tail_controls(&self, ui: &mut egui::Ui, tab: &mut WorkspaceTab) {
+        let source = tab.source.clone();
+
+        let toggled = ui
+            .add_enabled(source.is_some(), egui::Checkbox::new(&mut tab.tail.enabled, "Tail"))
+            .on_hover_text("Poll the tab's measurement for new points")
+            .changed();
+        if toggled {
+            if tab.tail.enabled {
+                // Pages load oldest first, so the first poll starts over from the latest points
+                tab.reset_data(source.clone());
+                let tail = &mut tab.tail;
+                tail.source = source;
+                tail.paused = false;
+                tail.last_poll = None;
+                tail.scroll_to_end = true;
+            } else {
+                tab.tail.stop();
+            }
+        }
+        let tail = &mut tab.tail;
+
+        if tail.enabled {
+            ui.label("every");
+            ui.add(egui::DragValue::new(&mut tail.interval_secs).range(1..=3600).suffix(" s"));
+            ui.label("keep");
+            ui.add(egui::DragValue::new(&mut tail.max_rows).range(100..=100_000).suffix(" rows"));
+            if tail.paused {
+                ui.colored_label(ui.visuals().warn_fg_color, "Paused");
+                if ui.button("Resume").clicked() {
+                    tail.paused = false;
+                    tail.scroll_to_end = true;
+                }
+            } else if tail.polling {
+                ui.spinner();
+            }
+        }
+    }
+
+    /// Called every frame; starts a poll for every tab that is due, background tabs included.
+    pub(crate) fn poll_tail(&self, ctx: &egui::Context, state: &mut AppState) {
+        for tab in &mut state.tabs {
+            self.poll_tab_tail(ctx, tab);
+        }
+    }
+
+    fn poll_tab_tail(&self, ctx: &egui::Context, tab: &mut WorkspaceTab) {
+        if !tab.tail.enabled {
+            return;
+        }
+        // Loading another measurement or series into the tab ends the tail
+        if tab.tail.source != tab.source {
+            tab.tail.stop();
+            return;
+        }
+        if tab.tail.highlight_from(0).is_some() {
+            ctx.request_repaint_after(HIGHLIGHT);
+        }
+        if tab.tail.paused || tab.tail.polling {
+            return;
+        }
+
+        let interval = Duration::from_secs(tab.tail.interval_secs);
+        if let Some(last) = tab.tail.last_poll {
+            if last.elapsed() < interval {
+                ctx.request_repaint_after(interval - last.elapsed());
+                return;
+            }
+        }
+
+        let (Some(client), Some(source)) = (tab.client.clone(), tab.tail.source.clone()) else {
+            return;
+        };
+        let last_time = tab
+            .current_columns
+            .iter()
+            .position(|c| c == "time")
+            .and_then(|i| tab.current_data.last()?.get(i)?.as_str().map(String::from));
+
+        tab.tail.polling = true;
+        tab.tail.last_poll = Some(Instant::now());
+        let max_rows = tab.tail.max_rows;
+        let tab_id = tab.id;
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        self.runtime.spawn(async move {
+            Self::fetch_tail(state_clone, ctx_clone, tab_id, client, source, last_time, max_rows).await;
+        });
+    }
+
+    async fn fetch_tail(
+        state: Arc<Mutex<AppState>>,
+        ctx: egui::Context,
+        tab_id: u64,
+        client: InfluxClient,
+        source: DataSource,
+        last_time: Option<String>,
+        max_rows: usize,
+    ) {
+        let query = match &last_time {
+            Some(time) => source.select_after(time, max_rows),
+            None => source.select_latest(max_rows),
+        };
+        let result = client.query(&query, Some(&source.db)).await;
+
+        {
+            let mut state = state.lock().unwrap();
+            let Some(tab) = state.tab_by_id(tab_id) else {
+                return;
+            };
+            tab.tail.polling = false;
+            tab.tail.last_poll = Some(Instant::now());
+            if !tab.tail.enabled || tab.tail.source.as_ref() != Some(&source) {
+                return;
+            }
+
+            match result {
+                Ok(Some((columns, mut rows))) => {
+                    let count = rows.len();
+                    if last_time.is_none() {
+                        rows.reverse();
+                        tab.update_data(columns, rows);
+                        tab.tail.scroll_to_end = true;
+                    } else {
+                        tab.append_rows(&columns, rows);
+                    }
+                    tab.keep_last_rows(max_rows);
+                    tab.offset = tab.current_data.len();
+                    tab.tail.new_rows = count.min(tab.current_data.len());
+                    tab.tail.new_since = Some(Instant::now());
+                    tab.status = format!(
+                        "Tail: {} new rows at {}",
+                        count,
+                        chrono::Local::now().format("%H:%M:%S")
+                    );
+                }
+                Ok(None) => {
+                    tab.status = format!("Tail: no new rows at {}", chrono::Local::now().format("%H:%M:%S"));
+                }
+                Err(e) => tab.status = format!("Error: {}", e),
+            }
+        }
+        ctx.request_repaint();
+    }
+}
@@ -1,21 +1,198 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::influx::InfluxClient;
+use crate::cardinality::CardinalityView;
+use crate::compare::SchemaCompareView;
+use crate::connections::Connection;
+use crate::copy::CopyView;
+use crate::credentials::{LoginForm, VaultPrompt};
+use crate::diagnostics::DiagnosticsView;
+use crate::downsample::DownsampleView;
+use crate::explain::ExplainView;
+use crate::gaps::GapView;
+use crate::continuous_query::CqEditor;
+use crate::influx::{self, ContinuousQuery, InfluxClient, RetentionPolicy};
+use crate::running_queries::RunningQueriesView;
+use crate::schema::MeasurementSchema;
+use crate::server::{Capabilities, ServerInfo};
+use crate::shards::ShardsView;
+use crate::users::UsersView;
+use crate::workspace::WorkspaceTab;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryLanguage {
+    InfluxQL,
+    Flux,
+    Sql,
+}
+
+impl QueryLanguage {
+    pub const ALL: [QueryLanguage; 3] = [QueryLanguage::InfluxQL, QueryLanguage::Flux, QueryLanguage::Sql];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QueryLanguage::InfluxQL => "InfluxQL",
+            QueryLanguage::Flux => "Flux",
+            QueryLanguage::Sql => "SQL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicyAction {
+    Create,
+    Alter,
+    Drop,
+}
+
+pub struct RetentionPolicyDialog {
+    /// Connection the dialog was opened on
+    pub connection: u64,
+    pub action: RetentionPolicyAction,
+    pub database: String,
+    pub policy: RetentionPolicy,
+    pub confirm: String,
+}
+
+pub enum AdminAction {
+    CreateDatabase { name: String, with_rp: bool, rp: RetentionPolicy },
+    DropDatabase { name: String },
+    DropMeasurement { database: String, measurement: String },
+    DropSeries { database: String, measurement: String, predicate: String },
+}
+
+impl AdminAction {
+    /// Name the user has to type to confirm a destructive action.
+    pub fn confirmation_name(&self) -> Option<&str> {
+        match self {
+            AdminAction::CreateDatabase { .. } => None,
+            AdminAction::DropDatabase { name } => Some(name),
+            AdminAction::DropMeasurement { measurement, .. } => Some(measurement),
+            AdminAction::DropSeries { measurement, .. } => Some(measurement),
+        }
+    }
+}
+
+pub struct AdminDialog {
+    /// Connection the dialog was opened on
+    pub connection: u64,
+    pub action: AdminAction,
+    pub confirm: String,
+}
+
+/// What `SELECT * FROM ...` reads for the table: a measurement, optionally narrowed to one series.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSource {
+    pub db: String,
+    pub rp: Option<String>,
+    pub measurement: String,
+    pub series: Option<String>,
+    /// All tag keys of the measurement, to pin the series exactly
+    #[serde(default)]
+    pub tag_keys: Vec<String>,
+}
+
+impl DataSource {
+    pub fn select(&self, limit: usize, offset: usize) -> String {
+        format!("{} LIMIT {} OFFSET {}", self.select_where(None), limit, offset)
+    }
+
+    /// Points newer than `after` (an RFC3339 time from a previous result), oldest first.
+    pub fn select_after(&self, after: &str, limit: usize) -> String {
+        let condition = format!("time > {}", influx::quote_literal(after));
+        format!("{} ORDER BY time ASC LIMIT {}", self.select_where(Some(condition)), limit)
+    }
+
+    /// The newest points, newest first.
+    pub fn select_latest(&self, limit: usize) -> String {
+        format!("{} ORDER BY time DESC LIMIT {}", self.select_where(None), limit)
+    }
+
+    fn select_where(&self, condition: Option<String>) -> String {
+        self.select_fields("*", condition)
+    }
+
+    /// `SELECT <fields>` from the measurement, narrowed to the series and `condition`.
+    pub fn select_fields(&self, fields: &str, condition: Option<String>) -> String {
+        let mut query = format!(
+            "SELECT {} FROM {}",
+            fields,
+            influx::qualified_measurement(&self.db, self.rp.as_deref(), &self.measurement)
+        );
+        let mut conditions: Vec<String> = condition.into_iter().collect();
+        if let Some(series) = &self.series {
+            let predicate = influx::series_predicate(series, &self.tag_keys);
+            if !predicate.is_empty() {
+                conditions.push(predicate);
+            }
+        }
+        if !conditions.is_empty() {
+            query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        query
+    }
+
+    pub fn label(&self) -> &str {
+        &self.measurement
+    }
+}
+
+/// Which numeric columns of the result are drawn above the table.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChartSettings {
+    pub visible: bool,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CentralTab {
+    Data,
+    Schema,
+}
 
 pub struct AppState {
     pub host: String,
     pub proxy: String,
-    pub databases: Vec<String>,
-    pub measurements: Vec<String>,
+    pub login: LoginForm,
+    /// Entered once per run, never saved
+    pub vault_passphrase: Option<String>,
+    pub vault_prompt: Option<VaultPrompt>,
+    pub rerun_on_startup: bool,
+    pub connections: Vec<Connection>,
+    pub active_connection: Option<u64>,
+    pub connection_counter: u64,
     pub selected_db: Option<String>,
+    pub selected_rp: Option<String>,
+    pub continuous_queries: Vec<ContinuousQuery>,
+    pub cq_editor: Option<CqEditor>,
+    pub rp_dialog: Option<RetentionPolicyDialog>,
+    pub admin_dialog: Option<AdminDialog>,
+    pub cardinality: Option<CardinalityView>,
+    pub schema_compare: Option<SchemaCompareView>,
+    pub copy_job: Option<CopyView>,
+    pub downsample: Option<DownsampleView>,
+    pub users: Option<UsersView>,
+    pub running_queries: Option<RunningQueriesView>,
+    pub diagnostics: Option<DiagnosticsView>,
+    pub shards: Option<ShardsView>,
+    pub gaps: Option<GapView>,
+    pub explain: Option<ExplainView>,
     pub selected_measurement: Option<String>,
-    pub current_columns: Vec<String>,
-    pub current_data: Vec<Vec<Value>>,
-    pub current_data_strings: Vec<Vec<String>>, // Cached string representation
-    pub custom_query: String,
+    pub series_keys: Vec<String>,
+    pub series_filter: String,
+    pub series_offset: usize,
+    pub selected_series: Option<String>,
+    /// Tag keys of the selected measurement, loaded with its series
+    pub series_tag_keys: Vec<String>,
+    pub central_tab: CentralTab,
+    pub schema: Option<MeasurementSchema>,
+    /// Connection, database and measurement the schema was last requested for
+    pub schema_requested: Option<(u64, String, String)>,
+    pub schema_loading: bool,
+    pub tabs: Vec<WorkspaceTab>,
+    pub active_tab: u64,
+    pub tab_counter: u64,
     pub status: String,
     pub is_loading: bool,
-    pub offset: usize,
-    pub client: Option<InfluxClient>,
 }
 
 impl Default for AppState {
@@ -23,42 +200,191 @@ impl Default for AppState {
         Self {
             host: String::new(),
             proxy: String::new(),
-            databases: Vec::new(),
-            measurements: Vec::new(),
+            login: LoginForm::default(),
+            vault_passphrase: None,
+            vault_prompt: None,
+            rerun_on_startup: false,
+            connections: Vec::new(),
+            active_connection: None,
+            connection_counter: 0,
             selected_db: None,
+            selected_rp: None,
+            continuous_queries: Vec::new(),
+            cq_editor: None,
+            rp_dialog: None,
+            admin_dialog: None,
+            cardinality: None,
+            schema_compare: None,
+            copy_job: None,
+            downsample: None,
+            users: None,
+            running_queries: None,
+            diagnostics: None,
+            shards: None,
+            gaps: None,
+            explain: None,
             selected_measurement: None,
-            current_columns: Vec::new(),
-            current_data: Vec::new(),
-            current_data_strings: Vec::new(),
-            custom_query: String::new(),
+            series_keys: Vec::new(),
+            series_filter: String::new(),
+            series_offset: 0,
+            selected_series: None,
+            series_tag_keys: Vec::new(),
+            central_tab: CentralTab::Data,
+            schema: None,
+            schema_requested: None,
+            schema_loading: false,
+            tabs: vec![WorkspaceTab::new(1, "Tab 1".to_string())],
+            active_tab: 1,
+            tab_counter: 1,
             status: "Ready".to_string(),
             is_loading: false,
-            offset: 0,
-            client: None,
         }
     }
 }
 
 impl AppState {
-    pub fn update_data(&mut self, columns: Vec<String>, data: Vec<Vec<Value>>) {
-        self.current_columns = columns;
-        // Pre-convert all values to strings for faster rendering
-        self.current_data_strings = data.iter()
-            .map(|row| row.iter().map(|v| value_to_string(v)).collect())
-            .collect();
-        self.current_data = data;
-    }
-
-    pub fn extend_data(&mut self, data: Vec<Vec<Value>>) {
-        let new_strings: Vec<Vec<String>> = data.iter()
-            .map(|row| row.iter().map(|v| value_to_string(v)).collect())
-            .collect();
-        self.current_data_strings.extend(new_strings);
-        self.current_data.extend(data);
+    /// The connection the sidebar selection, tools and dialogs work on.
+    pub fn connection(&self) -> Option<&Connection> {
+        let id = self.active_connection?;
+        self.connections.iter().find(|c| c.id == id)
+    }
+
+    /// The connection a tool window was opened on; `None` once it is disconnected.
+    pub fn connection_of(&self, id: u64) -> Option<&Connection> {
+        self.connections.iter().find(|c| c.id == id)
+    }
+
+    pub fn connection_by_id(&mut self, id: u64) -> Option<&mut Connection> {
+        self.connections.iter_mut().find(|c| c.id == id)
+    }
+
+    /// Makes `id` the active connection; the selection below it starts over when it changes.
+    pub fn select_connection(&mut self, id: u64) {
+        if self.active_connection == Some(id) {
+            return;
+        }
+        self.active_connection = Some(id);
+        self.selected_db = None;
+        self.selected_rp = None;
+        self.selected_measurement = None;
+        self.selected_series = None;
+        self.series_keys.clear();
+        self.series_tag_keys.clear();
+        self.series_offset = 0;
+        self.continuous_queries.clear();
+        self.schema = None;
+        self.schema_requested = None;
+        self.schema_loading = false;
+    }
+
+    pub fn client(&self) -> Option<InfluxClient> {
+        self.connection().map(|c| c.client.clone())
+    }
+
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.connection()?.server_info.as_ref()
+    }
+
+    /// Retention policies of the selected database, once listed.
+    pub fn retention_policies(&self) -> &[RetentionPolicy] {
+        self.connection()
+            .zip(self.selected_db.as_ref())
+            .and_then(|(c, db)| c.retention_policies.get(db))
+            .map(|p| p.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.connection()
+            .map(|c| c.capabilities())
+            .unwrap_or(Capabilities::ALL)
+    }
+
+    /// Whether the active connection was marked read-only.
+    pub fn read_only(&self) -> bool {
+        self.connection().is_some_and(|c| c.read_only)
+    }
+
+    pub fn can_administer(&self) -> bool {
+        !self.read_only() && self.capabilities().manage_databases
+    }
+
+    pub fn data_source(&self) -> Option<DataSource> {
+        Some(DataSource {
+            db: self.selected_db.clone()?,
+            rp: self.selected_rp.clone(),
+            measurement: self.selected_measurement.clone()?,
+            series: self.selected_series.clone(),
+            tag_keys: match self.selected_series {
+                Some(_) => self.series_tag_keys.clone(),
+                None => Vec::new(),
+            },
+        })
+    }
+
+    pub fn tab(&self) -> &WorkspaceTab {
+        self.tabs.iter().find(|t| t.id == self.active_tab).unwrap_or(&self.tabs[0])
+    }
+
+    pub fn tab_mut(&mut self) -> &mut WorkspaceTab {
+        let index = self.tabs.iter().position(|t| t.id == self.active_tab).unwrap_or(0);
+        &mut self.tabs[index]
+    }
+
+    /// The tab an async load was started for, unless it has been closed since.
+    pub fn tab_by_id(&mut self, id: u64) -> Option<&mut WorkspaceTab> {
+        self.tabs.iter_mut().find(|t| t.id == id)
+    }
+
+    pub fn next_tab_id(&mut self) -> u64 {
+        self.tab_counter += 1;
+        self.tab_counter
+    }
+
+    /// Opens and activates an empty tab on the current connection and database.
+    pub fn open_tab(&mut self) -> &mut WorkspaceTab {
+        let id = self.next_tab_id();
+        let mut tab = WorkspaceTab::new(id, format!("Tab {}", id));
+        tab.client = self.client();
+        tab.database = self.selected_db.clone();
+        self.tabs.push(tab);
+        self.active_tab = id;
+        self.tabs.last_mut().unwrap()
+    }
+
+    /// Closes a tab; closing the last one leaves a fresh empty tab behind.
+    pub fn close_tab(&mut self, id: u64) {
+        let Some(index) = self.tabs.iter().position(|t| t.id == id) else {
+            return;
+        };
+        self.tabs.remove(index);
+        if self.tabs.is_empty() {
+            self.open_tab();
+        } else if self.active_tab == id {
+            self.active_tab = self.tabs[index.min(self.tabs.len() - 1)].id;
+        }
+    }
+
+    /// Points the active tab at the sidebar's connection, e.g. before loading a measurement into it.
+    pub fn attach_active_tab(&mut self) {
+        let client = self.client();
+        let db = self.selected_db.clone();
+        let tab = self.tab_mut();
+        tab.client = client;
+        tab.database = db;
+    }
+
+    pub fn language_supported(&self, language: QueryLanguage) -> bool {
+        let caps = self.capabilities();
+        match language {
+            QueryLanguage::InfluxQL => caps.influxql,
+            QueryLanguage::Flux => caps.flux,
+            QueryLanguage::Sql => caps.sql,
+        }
     }
 }
 
-fn value_to_string(value: &Value) -> String {
+pub fn value_to_string(value: &Value) -> String {
     match value {
         Value::String(s) => s.clone(),
         Value::Number(n) => n.to_string(),
@@ -0,0 +1,304 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::influx::{InfluxClient, Privilege};
+use crate::ui::AppState;
+use crate::InfluxDBApp;
+
+pub struct UserRow {
+    pub name: String,
+    pub admin: bool,
+    pub grants: HashMap<String, Privilege>,
+}
+
+#[derive(Default)]
+pub struct UsersView {
+    /// Connection the window was opened on
+    pub connection: u64,
+    pub users: Vec<UserRow>,
+    pub new_name: String,
+    pub new_password: String,
+    pub new_admin: bool,
+    /// User whose password is being changed, and the new password
+    pub password_for: Option<(String, String)>,
+    /// User about to be dropped, and the typed confirmation
+    pub drop_user: Option<(String, String)>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+enum UserAction {
+    Create { name: String, password: String, admin: bool },
+    SetPassword { name: String, password: String },
+    SetAdmin { name: String, admin: bool },
+    SetPrivilege { name: String, database: String, current: Option<Privilege>, privilege: Option<Privilege> },
+    Drop { name: String },
+}
+
+impl UserAction {
+    /// What to show in the status bar; never includes a password.
+    fn describe(&self) -> String {
+        match self {
+            UserAction::Create { name, .. } => format!("Created user {}", name),
+            UserAction::SetPassword { name, .. } => format!("Changed password of {}", name),
+            UserAction::SetAdmin { name, admin: true } => format!("Granted admin to {}", name),
+            UserAction::SetAdmin { name, admin: false } => format!("Revoked admin from {}", name),
+            UserAction::SetPrivilege { name, database, privilege, .. } => format!(
+                "Set {} on {} for {}",
+                privilege.map(|p| p.label()).unwrap_or("no access"),
+                database,
+                name
+            ),
+            UserAction::Drop { name } => format!("Dropped user {}", name),
+        }
+    }
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_users_window(&self, ctx: &egui::Context, state: &mut AppState) {
+        let Some(id) = state.users.as_ref().map(|v| v.connection) else {
+            return;
+        };
+        let Some(connection) = state.connection_of(id) else {
+            state.users = None;
+            return;
+        };
+        let client = connection.client.clone();
+        let editable = !connection.read_only;
+        let databases = connection.databases.clone();
+        let Some(view) = &mut state.users else {
+            return;
+        };
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut action = None;
+
+        egui::Window::new(format!("Users and privileges - {}", client.host()))
+            .id(egui::Id::new("users_window"))
+            .open(&mut open)
+            .default_size([700.0, 450.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                    if view.loading {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(err) = &view.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+
+                if editable {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("New user:");
+                        ui.add(egui::TextEdit::singleline(&mut view.new_name).hint_text("name").desired_width(120.0));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut view.new_password)
+                                .password(true)
+                                .hint_text("password")
+                                .desired_width(120.0),
+                        );
+                        ui.checkbox(&mut view.new_admin, "Admin");
+                        let ready = !view.new_name.trim().is_empty() && !view.new_password.is_empty();
+                        if ui.add_enabled(ready && !view.loading, egui::Button::new("Create")).clicked() {
+                            action = Some(UserAction::Create {
+                                name: view.new_name.trim().to_string(),
+                                password: std::mem::take(&mut view.new_password),
+                                admin: view.new_admin,
+                            });
+                            view.new_name.clear();
+                            view.new_admin = false;
+                        }
+                    });
+                }
+
+                if let Some((name, password)) = &mut view.password_for {
+                    let mut done = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("New password for {}:", name));
+                        ui.add(egui::TextEdit::singleline(password).password(true).desired_width(150.0));
+                        if ui.add_enabled(!password.is_empty(), egui::Button::new("Set")).clicked() {
+                            action = Some(UserAction::SetPassword {
+                                name: name.clone(),
+                                password: std::mem::take(password),
+                            });
+                            done = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            done = true;
+                        }
+                    });
+                    if done {
+                        view.password_for = None;
+                    }
+                }
+
+                if let Some((name, confirm)) = &mut view.drop_user {
+                    let mut done = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Type \"{}\" to drop the user:", name));
+                        ui.add(egui::TextEdit::singleline(confirm).desired_width(150.0));
+                        if ui.add_enabled(confirm == name, egui::Button::new("Drop")).clicked() {
+                            action = Some(UserAction::Drop { name: name.clone() });
+                            done = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            done = true;
+                        }
+                    });
+                    if done {
+                        view.drop_user = None;
+                    }
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("users_matrix").striped(true).show(ui, |ui| {
+                        ui.strong("User");
+                        ui.strong("Admin");
+                        for db in &databases {
+                            ui.strong(db);
+                        }
+                        ui.label("");
+                        ui.end_row();
+
+                        for user in &view.users {
+                            ui.label(&user.name);
+
+                            let mut admin = user.admin;
+                            if ui.add_enabled(editable, egui::Checkbox::without_text(&mut admin)).changed() {
+                                action = Some(UserAction::SetAdmin {
+                                    name: user.name.clone(),
+                                    admin,
+                                });
+                            }
+
+                            for db in &databases {
+                                // Admins can do everything everywhere; per-database grants don't apply
+                                if user.admin {
+                                    ui.weak("ALL");
+                                    continue;
+                                }
+                                let current = user.grants.get(db).copied();
+                                let mut selected = current;
+                                ui.add_enabled_ui(editable, |ui| {
+                                    egui::ComboBox::from_id_salt(("privilege", &user.name, db))
+                                        .selected_text(selected.map(|p| p.label()).unwrap_or("-"))
+                                        .width(70.0)
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(&mut selected, None, "-");
+                                            for privilege in Privilege::ALL {
+                                                ui.selectable_value(&mut selected, Some(privilege), privilege.label());
+                                            }
+                                        });
+                                });
+                                if selected != current {
+                                    action = Some(UserAction::SetPrivilege {
+                                        name: user.name.clone(),
+                                        database: db.clone(),
+                                        current,
+                                        privilege: selected,
+                                    });
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                if !editable {
+                                    return;
+                                }
+                                if ui.small_button("Password...").clicked() {
+                                    view.password_for = Some((user.name.clone(), String::new()));
+                                }
+                                if ui.small_button("Drop...").clicked() {
+                                    view.drop_user = Some((user.name.clone(), String::new()));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        if !open {
+            state.users = None;
+            return;
+        }
+
+        let state_clone = Arc::clone(&self.state);
+        let ctx_clone = ctx.clone();
+
+        if let Some(action) = action {
+            view.loading = true;
+            view.error = None;
+            self.runtime.spawn(async move {
+                let result = match &action {
+                    UserAction::Create { name, password, admin } => client.create_user(name, password, *admin).await,
+                    UserAction::SetPassword { name, password } => client.set_password(name, password).await,
+                    UserAction::SetAdmin { name, admin } => client.set_admin(name, *admin).await,
+                    UserAction::SetPrivilege { name, database, current, privilege } => {
+                        client.set_privilege(database, name, *current, *privilege).await
+                    }
+                    UserAction::Drop { name } => client.drop_user(name).await,
+                };
+
+                {
+                    let mut state = state_clone.lock().unwrap();
+                    match result {
+                        Ok(()) => state.status = action.describe(),
+                        Err(e) => {
+                            if let Some(view) = &mut state.users {
+                                view.error = Some(format!("Error: {}", e));
+                            }
+                        }
+                    }
+                }
+                Self::load_users(state_clone, ctx_clone, client).await;
+            });
+        } else if refresh {
+            view.loading = true;
+            view.error = None;
+            self.runtime.spawn(async move {
+                Self::load_users(state_clone, ctx_clone, client).await;
+            });
+        }
+    }
+
+    pub(crate) async fn load_users(state: Arc<Mutex<AppState>>, ctx: egui::Context, client: InfluxClient) {
+        let result = async {
+            let mut rows = Vec::new();
+            for user in client.show_users().await? {
+                let grants = if user.admin {
+                    HashMap::new()
+                } else {
+                    client.show_grants(&user.name).await?.into_iter().collect()
+                };
+                rows.push(UserRow {
+                    name: user.name,
+                    admin: user.admin,
+                    grants,
+                });
+            }
+            anyhow::Ok(rows)
+        }
+        .await;
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(view) = &mut state.users {
+                view.loading = false;
+                match result {
+                    Ok(rows) => view.users = rows,
+                    Err(e) => view.error = Some(format!("Error: {}", e)),
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
@@ -0,0 +1,318 @@
+use eframe::egui;
+use serde_json::Value;
+
+use crate::distribution::DistributionView;
+use crate::flux::{self, FluxTable};
+use crate::influx::{InfluxClient, SqlFormat};
+use crate::stats::ResultStats;
+use crate::tail::TailState;
+use crate::ui::{value_to_string, AppState, ChartSettings, DataSource, QueryLanguage};
+use crate::InfluxDBApp;
+
+/// One workspace tab: its own connection, query and result, loaded independently of the others.
+pub struct WorkspaceTab {
+    /// Stable across reordering, so async loads find their tab
+    pub id: u64,
+    pub title: String,
+    /// Set once the user renamed the tab; loading a measurement no longer retitles it
+    pub renamed: bool,
+    pub renaming: bool,
+    pub client: Option<InfluxClient>,
+    pub database: Option<String>,
+    pub source: Option<DataSource>,
+    /// The query behind the current result when it came from Execute rather than `source`
+    pub executed_query: Option<String>,
+    pub current_columns: Vec<String>,
+    pub current_data: Vec<Vec<Value>>,
+    pub current_data_strings: Vec<Vec<String>>, // Cached string representation
+    pub custom_query: String,
+    pub query_language: QueryLanguage,
+    pub sql_format: SqlFormat,
+    pub flux_tables: Vec<FluxTable>,
+    pub flux_flatten: bool,
+    pub chart: ChartSettings,
+    pub stats: ResultStats,
+    pub distribution: DistributionView,
+    pub tail: TailState,
+    pub status: String,
+    pub is_loading: bool,
+    pub offset: usize,
+}
+
+impl WorkspaceTab {
+    pub fn new(id: u64, title: String) -> Self {
+        Self {
+            id,
+            title,
+            renamed: false,
+            renaming: false,
+            client: None,
+            database: None,
+            source: None,
+            executed_query: None,
+            current_columns: Vec::new(),
+            current_data: Vec::new(),
+            current_data_strings: Vec::new(),
+            custom_query: String::new(),
+            query_language: QueryLanguage::InfluxQL,
+            sql_format: SqlFormat::Json,
+            flux_tables: Vec::new(),
+            flux_flatten: false,
+            chart: ChartSettings::default(),
+            stats: ResultStats::default(),
+            distribution: DistributionView::default(),
+            tail: TailState::default(),
+            status: "Ready".to_string(),
+            is_loading: false,
+            offset: 0,
+        }
+    }
+
+    /// A copy of the tab's query and result; a running load or tail stays with the original.
+    pub fn duplicate(&self, id: u64) -> Self {
+        Self {
+            id,
+            title: format!("{} (copy)", self.title),
+            renamed: true,
+            renaming: false,
+            client: self.client.clone(),
+            database: self.database.clone(),
+            source: self.source.clone(),
+            executed_query: self.executed_query.clone(),
+            current_columns: self.current_columns.clone(),
+            current_data: self.current_data.clone(),
+            current_data_strings: self.current_data_strings.clone(),
+            custom_query: self.custom_query.clone(),
+            query_language: self.query_language,
+            sql_format: self.sql_format,
+            flux_tables: self.flux_tables.clone(),
+            flux_flatten: self.flux_flatten,
+            chart: self.chart.clone(),
+            stats: self.stats.clone(),
+            distribution: self.distribution.clone(),
+            tail: TailState::default(),
+            status: self.status.clone(),
+            is_loading: false,
+            offset: self.offset,
+        }
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.client.as_ref().map(|c| c.host())
+    }
+
+    /// Empties the result before a fresh load of `source`.
+    pub fn reset_data(&mut self, source: Option<DataSource>) {
+        self.source = source;
+        self.executed_query = None;
+        self.offset = 0;
+        self.current_data.clear();
+        self.current_data_strings.clear();
+        self.invalidate_analysis();
+    }
+
+    /// Rows were replaced or dropped, so aggregates kept over them start over.
+    fn invalidate_analysis(&mut self) {
+        self.stats.invalidate();
+        self.distribution.invalidate();
+    }
+
+    pub fn update_data(&mut self, columns: Vec<String>, data: Vec<Vec<Value>>) {
+        self.current_columns = columns;
+        // Pre-convert all values to strings for faster rendering
+        self.current_data_strings = data.iter()
+            .map(|row| row.iter().map(value_to_string).collect())
+            .collect();
+        self.current_data = data;
+        self.flux_tables.clear();
+        self.invalidate_analysis();
+    }
+
+    pub fn set_flux_tables(&mut self, tables: Vec<FluxTable>) {
+        // The flattened grid always backs current_data so exports and row counts keep working
+        let (columns, rows) = flux::flatten(&tables);
+        self.update_data(columns, rows);
+        self.flux_tables = tables;
+    }
+
+    /// Appends rows whose columns may differ from the current ones, e.g. when a new field shows up.
+    pub fn append_rows(&mut self, columns: &[String], data: Vec<Vec<Value>>) {
+        if columns == self.current_columns.as_slice() {
+            self.extend_data(data);
+            return;
+        }
+
+        for column in columns {
+            if !self.current_columns.contains(column) {
+                self.current_columns.push(column.clone());
+                for row in &mut self.current_data {
+                    row.push(Value::Null);
+                }
+                for row in &mut self.current_data_strings {
+                    row.push(value_to_string(&Value::Null));
+                }
+            }
+        }
+
+        let positions: Vec<Option<usize>> = self
+            .current_columns
+            .iter()
+            .map(|c| columns.iter().position(|n| n == c))
+            .collect();
+        let rows = data
+            .into_iter()
+            .map(|row| {
+                positions
+                    .iter()
+                    .map(|p| p.and_then(|i| row.get(i).cloned()).unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect();
+        self.extend_data(rows);
+    }
+
+    /// Drops the oldest rows so at most `max` are kept.
+    pub fn keep_last_rows(&mut self, max: usize) {
+        let excess = self.current_data.len().saturating_sub(max);
+        if excess > 0 {
+            self.current_data.drain(..excess);
+            self.current_data_strings.drain(..excess);
+            self.invalidate_analysis();
+        }
+    }
+
+    pub fn extend_data(&mut self, data: Vec<Vec<Value>>) {
+        let new_strings: Vec<Vec<String>> = data.iter()
+            .map(|row| row.iter().map(value_to_string).collect())
+            .collect();
+        self.current_data_strings.extend(new_strings);
+        self.current_data.extend(data);
+    }
+}
+
+enum TabAction {
+    Select(u64),
+    New,
+    Rename(u64),
+    Duplicate(u64),
+    Move(u64, isize),
+    Close(u64),
+    CloseOthers(u64),
+}
+
+impl InfluxDBApp {
+    pub(crate) fn show_tab_strip(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        let mut action = None;
+        let active = state.active_tab;
+        let count = state.tabs.len();
+
+        ui.horizontal_wrapped(|ui| {
+            for (i, tab) in state.tabs.iter_mut().enumerate() {
+                let rename_id = egui::Id::new(("tab_rename", tab.id));
+
+                if tab.renaming {
+                    let response = ui.add(egui::TextEdit::singleline(&mut tab.title).id(rename_id).desired_width(140.0));
+                    if response.lost_focus() {
+                        tab.renaming = false;
+                        tab.renamed = true;
+                        if tab.title.trim().is_empty() {
+                            tab.title = format!("Tab {}", tab.id);
+                        }
+                    }
+                } else {
+                    let hover = format!(
+                        "{}\n{}",
+                        tab.host().unwrap_or("Not connected"),
+                        tab.database.as_deref().unwrap_or("No database")
+                    );
+                    let response = ui.selectable_label(tab.id == active, &tab.title).on_hover_text(hover);
+                    if response.clicked() {
+                        action = Some(TabAction::Select(tab.id));
+                    }
+                    if response.double_clicked() {
+                        action = Some(TabAction::Rename(tab.id));
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("Rename").clicked() {
+                            action = Some(TabAction::Rename(tab.id));
+                            ui.close_menu();
+                        }
+                        if ui.button("Duplicate").clicked() {
+                            action = Some(TabAction::Duplicate(tab.id));
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui.add_enabled(i > 0, egui::Button::new("Move left")).clicked() {
+                            action = Some(TabAction::Move(tab.id, -1));
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(i + 1 < count, egui::Button::new("Move right")).clicked() {
+                            action = Some(TabAction::Move(tab.id, 1));
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui.button("Close").clicked() {
+                            action = Some(TabAction::Close(tab.id));
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(count > 1, egui::Button::new("Close others")).clicked() {
+                            action = Some(TabAction::CloseOthers(tab.id));
+                            ui.close_menu();
+                        }
+                    });
+                }
+
+                if tab.is_loading || tab.tail.polling {
+                    ui.spinner();
+                }
+                if ui.small_button("x").on_hover_text("Close tab").clicked() {
+                    action = Some(TabAction::Close(tab.id));
+                }
+                ui.separator();
+            }
+
+            if ui.small_button("+").on_hover_text("New tab").clicked() {
+                action = Some(TabAction::New);
+            }
+        });
+
+        let Some(action) = action else {
+            return;
+        };
+        let position = |state: &AppState, id: u64| state.tabs.iter().position(|t| t.id == id);
+
+        match action {
+            TabAction::Select(id) => state.active_tab = id,
+            TabAction::New => {
+                state.open_tab();
+            }
+            TabAction::Rename(id) => {
+                if let Some(i) = position(state, id) {
+                    state.tabs[i].renaming = true;
+                    ui.memory_mut(|m| m.request_focus(egui::Id::new(("tab_rename", id))));
+                }
+            }
+            TabAction::Duplicate(id) => {
+                if let Some(i) = position(state, id) {
+                    let new_id = state.next_tab_id();
+                    let copy = state.tabs[i].duplicate(new_id);
+                    state.active_tab = copy.id;
+                    state.tabs.insert(i + 1, copy);
+                }
+            }
+            TabAction::Move(id, delta) => {
+                if let Some(i) = position(state, id) {
+                    let target = i as isize + delta;
+                    if target >= 0 && (target as usize) < state.tabs.len() {
+                        state.tabs.swap(i, target as usize);
+                    }
+                }
+            }
+            TabAction::Close(id) => state.close_tab(id),
+            TabAction::CloseOthers(id) => {
+                state.tabs.retain(|t| t.id == id);
+                state.active_tab = id;
+            }
+        }
+    }
+}